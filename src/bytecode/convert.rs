@@ -0,0 +1,187 @@
+use crate::bytecode::{HashKey, Obj, Value, ValueMap};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+/// A value didn't have the type a conversion out of `Value` asked for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl ConversionError {
+    pub fn new(expected: &'static str, value: &Value) -> ConversionError {
+        ConversionError {
+            expected,
+            found: value.type_name(),
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Expected {}, got {}", self.expected, self.found)
+    }
+}
+
+impl Error for ConversionError {}
+
+impl From<ConversionError> for String {
+    fn from(err: ConversionError) -> String {
+        err.to_string()
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(num: i64) -> Value {
+        Value::Int(num)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(num: f64) -> Value {
+        Value::Number(num)
+    }
+}
+
+/// Strings made this way aren't interned, which only costs sharing: equality compares contents.
+impl From<String> for Value {
+    fn from(string: String) -> Value {
+        Value::Obj(Obj::String(Rc::new(string)))
+    }
+}
+
+impl From<&str> for Value {
+    fn from(string: &str) -> Value {
+        Value::from(string.to_owned())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Value {
+        option.map_or(Value::Nil, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Value {
+        Value::Obj(Obj::new_list(values.into_iter().map(Into::into).collect()))
+    }
+}
+
+impl<T: Into<Value>> From<HashMap<String, T>> for Value {
+    fn from(entries: HashMap<String, T>) -> Value {
+        let mut map = ValueMap::new();
+        for (key, value) in entries {
+            // Strings are always hashable
+            let key = HashKey::new(Value::from(key)).unwrap();
+            map.insert(key, value.into());
+        }
+        Value::Obj(Obj::new_map(map))
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            value => Err(ConversionError::new("bool", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(num) => Ok(num),
+            value => Err(ConversionError::new("int", &value)),
+        }
+    }
+}
+
+/// Ints widen to floats, the same way they do in arithmetic.
+impl TryFrom<Value> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Number(num) => Ok(num),
+            Value::Int(num) => Ok(num as f64),
+            value => Err(ConversionError::new("number", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Obj(Obj::String(string)) => Ok(string.as_ref().clone()),
+            value => Err(ConversionError::new("string", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = ConversionError>> TryFrom<Value> for Vec<T> {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Obj(Obj::List(list)) => list.borrow().iter().cloned().map(T::try_from).collect(),
+            value => Err(ConversionError::new("list", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = ConversionError>> TryFrom<Value> for HashMap<String, T> {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Obj(Obj::Map(map)) => map
+                .borrow()
+                .iter()
+                .map(|(key, value)| {
+                    let key = String::try_from(key.clone())?;
+                    Ok((key, T::try_from(value.clone())?))
+                })
+                .collect(),
+            value => Err(ConversionError::new("map", &value)),
+        }
+    }
+}
+
+/// `Option<Value>` already converts through `From`, so these are spelled out per type rather
+/// than written once for every `T`.
+macro_rules! option_try_from {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<Value> for Option<$ty> {
+                type Error = ConversionError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    match value {
+                        Value::Nil => Ok(None),
+                        value => <$ty>::try_from(value).map(Some),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+option_try_from!(bool, i64, f64, String);
@@ -0,0 +1,22 @@
+use std::fmt;
+
+/// A runtime error caught by a `catch` clause, so the script can inspect it.
+pub struct ErrorObject {
+    pub message: String,
+    pub line: usize,
+}
+
+impl ErrorObject {
+    pub fn new(message: &str, line: usize) -> ErrorObject {
+        ErrorObject {
+            message: message.to_owned(),
+            line,
+        }
+    }
+}
+
+impl fmt::Debug for ErrorObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<error: {}>", self.message)
+    }
+}
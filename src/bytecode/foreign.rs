@@ -0,0 +1,124 @@
+use crate::bytecode::{Arity, NativeResult, Value};
+use crate::vm::VM;
+use std::any::Any;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+pub type ForeignMethodFn = fn(&mut VM, &Foreign, &[Value]) -> NativeResult;
+pub type ForeignGetterFn = fn(&mut VM, &Foreign) -> NativeResult;
+pub type FinalizerFn = fn(&mut dyn Any);
+
+pub struct ForeignMethod {
+    pub arity: Arity,
+    pub function: ForeignMethodFn,
+}
+
+/// What scripts can do with a kind of foreign object: the methods they can call on it and the
+/// properties they can read from it.
+pub struct ForeignClass {
+    pub name: String,
+    methods: HashMap<String, ForeignMethod>,
+    getters: HashMap<String, ForeignGetterFn>,
+    finalizer: Option<FinalizerFn>,
+}
+
+impl ForeignClass {
+    pub fn new(name: &str) -> ForeignClass {
+        ForeignClass {
+            name: name.to_owned(),
+            methods: HashMap::new(),
+            getters: HashMap::new(),
+            finalizer: None,
+        }
+    }
+
+    pub fn method(mut self, name: &str, arity: Arity, function: ForeignMethodFn) -> Self {
+        self.methods
+            .insert(name.to_owned(), ForeignMethod { arity, function });
+        self
+    }
+
+    /// Makes `object.name` evaluate to whatever `getter` returns. Getters take priority over
+    /// methods of the same name.
+    pub fn getter(mut self, name: &str, getter: ForeignGetterFn) -> Self {
+        self.getters.insert(name.to_owned(), getter);
+        self
+    }
+
+    /// Runs `finalizer` on an object's data once the last reference to it goes away.
+    pub fn finalizer(mut self, finalizer: FinalizerFn) -> Self {
+        self.finalizer = Some(finalizer);
+        self
+    }
+
+    pub fn get_method(&self, name: &str) -> Option<&ForeignMethod> {
+        self.methods.get(name)
+    }
+
+    pub fn get_getter(&self, name: &str) -> Option<ForeignGetterFn> {
+        self.getters.get(name).copied()
+    }
+}
+
+/// Host data handed to scripts as an opaque object. Scripts only reach the data through the
+/// methods and getters of its class.
+pub struct Foreign {
+    pub class: Rc<ForeignClass>,
+    data: RefCell<Box<dyn Any>>,
+}
+
+impl Foreign {
+    pub fn new<T: Any>(class: Rc<ForeignClass>, data: T) -> Foreign {
+        Foreign {
+            class,
+            data: RefCell::new(Box::new(data)),
+        }
+    }
+
+    /// Borrows the data, if it's a `T`.
+    pub fn borrow<T: Any>(&self) -> Option<Ref<'_, T>> {
+        let data = self.data.borrow();
+        if data.is::<T>() {
+            Some(Ref::map(data, |data| data.downcast_ref::<T>().unwrap()))
+        } else {
+            None
+        }
+    }
+
+    pub fn borrow_mut<T: Any>(&self) -> Option<RefMut<'_, T>> {
+        let data = self.data.borrow_mut();
+        if data.is::<T>() {
+            Some(RefMut::map(data, |data| data.downcast_mut::<T>().unwrap()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for Foreign {
+    fn drop(&mut self) {
+        if let Some(finalizer) = self.class.finalizer {
+            finalizer(self.data.get_mut().as_mut());
+        }
+    }
+}
+
+impl fmt::Debug for Foreign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} object>", self.class.name)
+    }
+}
+
+/// A method looked up on a foreign object, remembering the object it'll be called on.
+pub struct BoundMethod {
+    pub receiver: Rc<Foreign>,
+    pub name: String,
+}
+
+impl fmt::Debug for BoundMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<method {}.{}>", self.receiver.class.name, self.name)
+    }
+}
@@ -0,0 +1,108 @@
+use crate::bytecode::{exact_int, Obj, Value};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// A `Value` that can be used as a map key. Only strings, numbers, bools and nil are hashable.
+#[derive(Clone, Debug)]
+pub struct HashKey(Value);
+
+impl HashKey {
+    pub fn new(value: Value) -> Result<HashKey, String> {
+        match &value {
+            Value::Nil | Value::Bool(_) | Value::Int(_) | Value::Obj(Obj::String(_)) => {
+                Ok(HashKey(value))
+            }
+            // NaN isn't equal to itself, so it could never be looked up again
+            Value::Number(num) if num.is_nan() => Err("NaN can't be used as a map key".to_owned()),
+            Value::Number(_) => Ok(HashKey(value)),
+            other => Err(format!("A {} can't be used as a map key", other.type_name())),
+        }
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.0
+    }
+}
+
+impl PartialEq for HashKey {
+    fn eq(&self, other: &HashKey) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for HashKey {}
+
+impl Hash for HashKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.0 {
+            Value::Nil => 0.hash(state),
+            Value::Bool(b) => b.hash(state),
+            Value::Int(num) => num.hash(state),
+            // Whole floats equal the int with the same value, so they have to hash the same too
+            Value::Number(num) => match exact_int(*num) {
+                Some(int) => int.hash(state),
+                None => num.to_bits().hash(state),
+            },
+            Value::Obj(Obj::String(s)) => s.hash(state),
+            Value::Obj(_) => unreachable!("HashKey::new only accepts hashable values"),
+        }
+    }
+}
+
+/// A hash map that iterates in insertion order.
+#[derive(Debug, Default)]
+pub struct ValueMap {
+    entries: Vec<(HashKey, Value)>,
+    indices: HashMap<HashKey, usize>,
+}
+
+impl ValueMap {
+    pub fn new() -> ValueMap {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Value> {
+        self.indices.get(key).map(|&index| &self.entries[index].1)
+    }
+
+    pub fn contains_key(&self, key: &HashKey) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Sets the value for `key`, keeping its original position if it was already present.
+    pub fn insert(&mut self, key: HashKey, value: Value) {
+        if let Some(&index) = self.indices.get(&key) {
+            self.entries[index].1 = value;
+        } else {
+            self.indices.insert(key.clone(), self.entries.len());
+            self.entries.push((key, value));
+        }
+    }
+
+    pub fn remove(&mut self, key: &HashKey) -> Option<Value> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+        for (_, later) in self.indices.iter_mut() {
+            if *later > index {
+                *later -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(key, _)| key.value())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.entries.iter().map(|(key, value)| (key.value(), value))
+    }
+}
@@ -1,4 +1,10 @@
 mod chunk;
+mod convert;
+mod error_object;
+mod foreign;
+mod map;
+mod module;
+mod native;
 mod obj;
 mod opcode;
 mod source_info;
@@ -7,6 +13,12 @@ mod value;
 mod variables;
 
 pub use chunk::*;
+pub use convert::*;
+pub use error_object::*;
+pub use foreign::*;
+pub use map::*;
+pub use module::*;
+pub use native::*;
 pub use obj::*;
 pub use opcode::*;
 pub use source_info::*;
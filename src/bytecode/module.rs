@@ -0,0 +1,16 @@
+use crate::bytecode::GlobalMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// A file that's been imported, along with the globals it defined while it ran.
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    pub globals: GlobalMap,
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
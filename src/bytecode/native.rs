@@ -0,0 +1,70 @@
+use crate::bytecode::Value;
+use crate::vm::VM;
+use std::fmt;
+
+pub type NativeResult = Result<Value, NativeError>;
+
+pub enum NativeError {
+    /// Becomes a runtime error with this message.
+    Error(String),
+    /// Stops the script with this exit code.
+    Exit(i32),
+}
+
+impl From<String> for NativeError {
+    fn from(message: String) -> NativeError {
+        NativeError::Error(message)
+    }
+}
+
+pub type NativeFn = fn(&mut VM, &[Value]) -> NativeResult;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+    Between(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, arg_count: usize) -> bool {
+        match *self {
+            Arity::Exactly(n) => arg_count == n,
+            Arity::AtLeast(min) => arg_count >= min,
+            Arity::Between(min, max) => arg_count >= min && arg_count <= max,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Arity::Exactly(n) => write!(f, "{}", n),
+            Arity::AtLeast(min) => write!(f, "at least {}", min),
+            Arity::Between(min, max) => write!(f, "{} to {}", min, max),
+        }
+    }
+}
+
+/// A function implemented in Rust that scripts can call like any other value.
+pub struct Native {
+    pub name: String,
+    pub arity: Arity,
+    pub function: NativeFn,
+}
+
+impl Native {
+    pub fn new(name: &str, arity: Arity, function: NativeFn) -> Native {
+        Native {
+            name: name.to_owned(),
+            arity,
+            function,
+        }
+    }
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
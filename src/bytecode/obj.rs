@@ -1,23 +1,158 @@
+use crate::bytecode::{
+    BoundMethod, ErrorObject, Foreign, ForeignClass, Module, Native, Value, ValueMap,
+};
+use std::any::Any;
+use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
 
+pub type List = Rc<RefCell<Vec<Value>>>;
+pub type Map = Rc<RefCell<ValueMap>>;
+
+/// The ints from `start` up to but not including `end`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Range {
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Obj {
     String(Rc<String>),
+    List(List),
+    Map(Map),
+    Range(Range),
+    Native(Rc<Native>),
+    Foreign(Rc<Foreign>),
+    BoundMethod(Rc<BoundMethod>),
+    Module(Rc<Module>),
+    Error(Rc<ErrorObject>),
+}
+
+impl Obj {
+    pub fn new_list(values: Vec<Value>) -> Obj {
+        Obj::List(Rc::new(RefCell::new(values)))
+    }
+
+    pub fn new_map(map: ValueMap) -> Obj {
+        Obj::Map(Rc::new(RefCell::new(map)))
+    }
+
+    pub fn new_foreign<T: Any>(class: &Rc<ForeignClass>, data: T) -> Obj {
+        Obj::Foreign(Rc::new(Foreign::new(class.clone(), data)))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Obj::String(_) => "string",
+            Obj::List(_) => "list",
+            Obj::Map(_) => "map",
+            Obj::Range(_) => "range",
+            Obj::Native(_) => "native function",
+            Obj::Foreign(_) => "foreign object",
+            Obj::BoundMethod(_) => "method",
+            Obj::Module(_) => "module",
+            Obj::Error(_) => "error",
+        }
+    }
 }
 
 impl PartialEq for Obj {
     fn eq(&self, other: &Obj) -> bool {
         match (self, other) {
-            (Obj::String(l), Obj::String(r)) => Rc::ptr_eq(l, r),
+            // Strings made by the host aren't interned, so fall back to comparing contents
+            (Obj::String(l), Obj::String(r)) => Rc::ptr_eq(l, r) || l == r,
+            (Obj::List(l), Obj::List(r)) => Rc::ptr_eq(l, r),
+            (Obj::Map(l), Obj::Map(r)) => Rc::ptr_eq(l, r),
+            (Obj::Range(l), Obj::Range(r)) => l == r,
+            (Obj::Native(l), Obj::Native(r)) => Rc::ptr_eq(l, r),
+            (Obj::Foreign(l), Obj::Foreign(r)) => Rc::ptr_eq(l, r),
+            (Obj::BoundMethod(l), Obj::BoundMethod(r)) => {
+                Rc::ptr_eq(&l.receiver, &r.receiver) && l.name == r.name
+            }
+            (Obj::Module(l), Obj::Module(r)) => Rc::ptr_eq(l, r),
+            (Obj::Error(l), Obj::Error(r)) => Rc::ptr_eq(l, r),
+            _ => false,
         }
     }
 }
 
 impl fmt::Display for Obj {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, &mut Vec::new())
+    }
+}
+
+impl Obj {
+    /// Writes the object, where `printing` holds the lists and maps that are already being written
+    /// further up. A container that contains itself prints as `[...]` or `{...}` the second time
+    /// round instead of recursing forever.
+    fn write(&self, f: &mut fmt::Formatter, printing: &mut Vec<*const ()>) -> fmt::Result {
         match self {
             Obj::String(s) => write!(f, "{}", s),
+            Obj::List(list) => {
+                let ptr = Rc::as_ptr(list) as *const ();
+                if printing.contains(&ptr) {
+                    return write!(f, "[...]");
+                }
+                printing.push(ptr);
+                write!(f, "[")?;
+                for (i, value) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, value, printing)?;
+                }
+                printing.pop();
+                write!(f, "]")
+            }
+            Obj::Map(map) => {
+                let ptr = Rc::as_ptr(map) as *const ();
+                if printing.contains(&ptr) {
+                    return write!(f, "{{...}}");
+                }
+                printing.push(ptr);
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, key, printing)?;
+                    write!(f, ": ")?;
+                    write_element(f, value, printing)?;
+                }
+                printing.pop();
+                write!(f, "}}")
+            }
+            Obj::Range(range) => write!(f, "{}..{}", range.start, range.end),
+            Obj::Native(native) => write!(f, "<native fn {}>", native.name),
+            Obj::Foreign(foreign) => write!(f, "{:?}", foreign),
+            Obj::BoundMethod(method) => write!(f, "{:?}", method),
+            Obj::Module(module) => write!(f, "{:?}", module),
+            Obj::Error(error) => write!(f, "{:?}", error),
         }
     }
 }
+
+/// Quotes strings inside collections, so ["a, b"] and ["a", "b"] look different
+fn write_element(
+    f: &mut fmt::Formatter,
+    value: &Value,
+    printing: &mut Vec<*const ()>,
+) -> fmt::Result {
+    match value {
+        Value::Obj(Obj::String(s)) => write!(f, "{:?}", s),
+        Value::Obj(obj) => obj.write(f, printing),
+        value => write!(f, "{}", value),
+    }
+}
@@ -13,6 +13,16 @@ pub enum Opcode {
     Sub,
     Mul,
     Div,
+    Mod,
+    Pow,
+    FloorDiv,
+    // Bitwise ops
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    Shl,
+    Shr,
     // Literals
     True,
     False,
@@ -20,8 +30,13 @@ pub enum Opcode {
     // Logical ops
     Not,
     Eq,
+    Ne,
     Gt,
+    Ge,
     Lt,
+    Le,
+    // Strings
+    Stringify,
     // Print
     Print,
     // Stack
@@ -36,6 +51,27 @@ pub enum Opcode {
     JZ,
     JMP,
     LOOP,
+    JumpTable,
+    // Collections
+    BuildList,
+    BuildMap,
+    BuildRange,
+    GetIndex,
+    SetIndex,
+    GetSlice,
+    // Iteration
+    IterInit,
+    IterNext,
+    // Calls
+    Call,
+    // Objects
+    GetProperty,
+    // Modules
+    Import,
+    // Exceptions
+    PushHandler,
+    PopHandler,
+    Throw,
 }
 
 impl fmt::Display for Opcode {
@@ -49,13 +85,26 @@ impl fmt::Display for Opcode {
             Sub => "SUB",
             Mul => "MUL",
             Div => "DIV",
+            Mod => "MOD",
+            Pow => "POW",
+            FloorDiv => "FLOOR_DIV",
+            BitAnd => "BIT_AND",
+            BitOr => "BIT_OR",
+            BitXor => "BIT_XOR",
+            BitNot => "BIT_NOT",
+            Shl => "SHL",
+            Shr => "SHR",
             True => "TRUE",
             False => "FALSE",
             Nil => "NIL",
             Not => "NOT",
             Eq => "EQ",
+            Ne => "NE",
             Gt => "GT",
+            Ge => "GE",
             Lt => "LT",
+            Le => "LE",
+            Stringify => "STRINGIFY",
             Print => "PRINT",
             Pop => "POP",
             DefineGlobal => "DEF_GLOBAL",
@@ -66,6 +115,21 @@ impl fmt::Display for Opcode {
             JZ => "JZ",
             JMP => "JMP",
             LOOP => "LOOP",
+            JumpTable => "JUMP_TABLE",
+            BuildList => "BUILD_LIST",
+            BuildMap => "BUILD_MAP",
+            BuildRange => "BUILD_RANGE",
+            GetIndex => "GET_INDEX",
+            SetIndex => "SET_INDEX",
+            GetSlice => "GET_SLICE",
+            IterInit => "ITER_INIT",
+            IterNext => "ITER_NEXT",
+            Call => "CALL",
+            GetProperty => "GET_PROPERTY",
+            Import => "IMPORT",
+            PushHandler => "PUSH_HANDLER",
+            PopHandler => "POP_HANDLER",
+            Throw => "THROW",
         };
         fmt::Display::fmt(string, f)
     }
@@ -97,23 +161,51 @@ impl TryFrom<u8> for Opcode {
             4 => Ok(Sub),
             5 => Ok(Mul),
             6 => Ok(Div),
-            7 => Ok(True),
-            8 => Ok(False),
-            9 => Ok(Nil),
-            10 => Ok(Not),
-            11 => Ok(Eq),
-            12 => Ok(Gt),
-            13 => Ok(Lt),
-            14 => Ok(Print),
-            15 => Ok(Pop),
-            16 => Ok(DefineGlobal),
-            17 => Ok(GetGlobal),
-            18 => Ok(SetGlobal),
-            19 => Ok(GetLocal),
-            20 => Ok(SetLocal),
-            21 => Ok(JZ),
-            22 => Ok(JMP),
-            23 => Ok(LOOP),
+            7 => Ok(Mod),
+            8 => Ok(Pow),
+            9 => Ok(FloorDiv),
+            10 => Ok(BitAnd),
+            11 => Ok(BitOr),
+            12 => Ok(BitXor),
+            13 => Ok(BitNot),
+            14 => Ok(Shl),
+            15 => Ok(Shr),
+            16 => Ok(True),
+            17 => Ok(False),
+            18 => Ok(Nil),
+            19 => Ok(Not),
+            20 => Ok(Eq),
+            21 => Ok(Ne),
+            22 => Ok(Gt),
+            23 => Ok(Ge),
+            24 => Ok(Lt),
+            25 => Ok(Le),
+            26 => Ok(Stringify),
+            27 => Ok(Print),
+            28 => Ok(Pop),
+            29 => Ok(DefineGlobal),
+            30 => Ok(GetGlobal),
+            31 => Ok(SetGlobal),
+            32 => Ok(GetLocal),
+            33 => Ok(SetLocal),
+            34 => Ok(JZ),
+            35 => Ok(JMP),
+            36 => Ok(LOOP),
+            37 => Ok(JumpTable),
+            38 => Ok(BuildList),
+            39 => Ok(BuildMap),
+            40 => Ok(BuildRange),
+            41 => Ok(GetIndex),
+            42 => Ok(SetIndex),
+            43 => Ok(GetSlice),
+            44 => Ok(IterInit),
+            45 => Ok(IterNext),
+            46 => Ok(Call),
+            47 => Ok(GetProperty),
+            48 => Ok(Import),
+            49 => Ok(PushHandler),
+            50 => Ok(PopHandler),
+            51 => Ok(Throw),
             _ => Err(()),
         }
     }
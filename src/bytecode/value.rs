@@ -9,6 +9,7 @@ pub(crate) type ConstantPointer = u8;
 pub enum Value {
     Nil,
     Bool(bool),
+    Int(i64),
     Number(f64),
     Obj(Obj),
 }
@@ -18,10 +19,36 @@ impl Value {
         match self {
             Value::Nil => true,
             Value::Bool(b) => !b,
+            Value::Int(n) => *n == 0,
             Value::Number(n) => *n == 0.0,
             Value::Obj(_) => false,
         }
     }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Int(_) | Value::Number(_))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Number(_) => "float",
+            Value::Obj(obj) => obj.type_name(),
+        }
+    }
+}
+
+/// Gets the int a float is exactly equal to, if there is one. Converting the int to a float
+/// instead would round ints above 2^53, making them equal to floats they're not equal to.
+pub(crate) fn exact_int(num: f64) -> Option<i64> {
+    // i64::MAX rounds up to 2^63 as a float, so that's the first float out of range
+    if num.fract() == 0.0 && num >= i64::MIN as f64 && num < i64::MAX as f64 {
+        Some(num as i64)
+    } else {
+        None
+    }
 }
 
 impl fmt::Display for Value {
@@ -29,7 +56,9 @@ impl fmt::Display for Value {
         match self {
             Value::Nil => write!(f, "Nil"),
             Value::Bool(b) => write!(f, "{}", b),
-            Value::Number(num) => write!(f, "{}", num),
+            Value::Int(num) => write!(f, "{}", num),
+            // Debug formatting keeps the ".0" on whole floats, so they can be told apart from ints
+            Value::Number(num) => write!(f, "{:?}", num),
             Value::Obj(obj) => write!(f, "{}", obj),
         }
     }
@@ -39,6 +68,10 @@ impl PartialEq for Value {
     fn eq(&self, other: &Value) -> bool {
         match (self, other) {
             (Value::Number(l), Value::Number(r)) => l == r,
+            (Value::Int(l), Value::Int(r)) => l == r,
+            (Value::Int(i), Value::Number(n)) | (Value::Number(n), Value::Int(i)) => {
+                exact_int(*n) == Some(*i)
+            }
             (Value::Bool(l), Value::Bool(r)) => l == r,
             (Value::Nil, Value::Nil) => true,
             (Value::Obj(l), Value::Obj(r)) => l == r,
@@ -47,17 +80,6 @@ impl PartialEq for Value {
     }
 }
 
-impl TryFrom<Value> for f64 {
-    type Error = ();
-
-    fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Number(num) => Ok(num),
-            _ => Err(()),
-        }
-    }
-}
-
 pub struct ValueArray {
     pub values: Vec<Value>,
 }
@@ -1,5 +1,5 @@
 use crate::bytecode::Value;
-use crate::compiler::{Token, TokenKind, Source};
+use crate::compiler::{Position, Source, Token, TokenKind};
 use std::collections::HashMap;
 
 pub type GlobalMap = HashMap<String, Value>;
@@ -66,6 +66,18 @@ impl LocalMap {
         Ok(())
     }
 
+    /// Reserves a slot for a value the compiler keeps on the stack but which can't be named from
+    /// source code.
+    pub fn add_hidden(&mut self) -> Result<u8, &'static str> {
+        if self.locals.len() >= u8::MAX as usize {
+            return Err("Too many local variables in current function");
+        }
+
+        let name = Token::new(TokenKind::Identifier, Position::new(0, 0, 0));
+        self.locals.push(Local::new(name, self.scope_depth));
+        Ok((self.locals.len() - 1) as u8)
+    }
+
     pub fn mark_initialized(&mut self) {
         if let Some(last) = self.locals.last_mut() {
             last.depth = self.scope_depth
@@ -73,7 +85,7 @@ impl LocalMap {
     }
 
     pub fn resolve(&self, name: &str, source: &Source) -> Option<u8> {
-        for (index, local) in self.locals.iter().rev().enumerate() {
+        for (index, local) in self.locals.iter().enumerate().rev() {
             if source.get_lexeme(&local.name) == name {
                 if local.depth == std::usize::MAX {
                     eprintln!("Cannot read variable in own initializer.");
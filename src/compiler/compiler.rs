@@ -1,49 +1,63 @@
 use crate::bytecode::{get_or_insert_string, Chunk, InternMap, LocalMap, Obj, Opcode, Value};
 use crate::compiler::{
-    CompileError, Keyword, ParseFn, ParseRule, Parser, Precedence, Scanner, Source, Token,
-    TokenKind,
+    unescape, CompileError, Keyword, ParseFn, ParseRule, Parser, Precedence, Scanner, Source,
+    SyntaxError, Token, TokenKind,
 };
-use crate::utils::PrettyPrinter;
 use std::fmt::Debug;
 
 #[cfg(feature = "print_code")]
 use crate::debug::Disassembler;
 use std::rc::Rc;
 use std::convert::TryInto;
+use std::path::Path;
 
 pub struct Compiler<'src> {
     source: Source<'src>,
     chunk: Chunk,
     scanner: Scanner<'src>,
     parser: Parser<'src>,
-    strings: InternMap,
+    /// The intern table shared with the VM, which keeps it even when compiling fails
+    strings: &'src mut InternMap,
     locals: LocalMap,
     pub had_error: bool,
+    errors: Vec<SyntaxError>,
     pub panic_mode: bool,
 }
 
-pub type CompileResult = Result<(Chunk, InternMap, LocalMap), CompileError>;
+pub type CompileResult = Result<(Chunk, LocalMap), CompileError>;
 
 impl<'src> Compiler<'src> {
-    pub fn new(source: Source<'src>) -> Compiler<'src> {
+    /// Creates a compiler that interns its string constants into an existing table, so they're
+    /// shared with strings from earlier compilations.
+    pub fn with_strings(source: Source<'src>, strings: &'src mut InternMap) -> Compiler<'src> {
         Compiler {
             source,
             chunk: Chunk::new(),
             scanner: Scanner::new(source),
             parser: Parser::new(source),
-            strings: InternMap::new(),
+            strings,
             locals: LocalMap::new(),
             had_error: false,
+            errors: Vec::new(),
             panic_mode: false,
         }
     }
 
     pub fn try_consume(&mut self, token_kind: &TokenKind) -> bool {
-        self.parser.try_consume(&mut self.scanner, token_kind)
+        if self.parser.check(token_kind) {
+            self.advance();
+            true
+        } else {
+            false
+        }
     }
 
     pub fn consume(&mut self, token_kind: &TokenKind, message: &str) {
-        self.parser.consume(&mut self.scanner, token_kind, message);
+        if self.parser.check(token_kind) {
+            self.advance();
+        } else {
+            self.do_error(message);
+        }
     }
 
     pub fn compile(mut self) -> CompileResult {
@@ -64,15 +78,19 @@ impl<'src> Compiler<'src> {
         }
 
         if self.had_error {
-            Err(CompileError {})
+            Err(CompileError {
+                errors: self.errors,
+            })
         } else {
-            Ok((self.chunk, self.strings, self.locals))
+            Ok((self.chunk, self.locals))
         }
     }
 
     pub fn declaration(&mut self) {
         if self.try_consume(&TokenKind::Keyword(Keyword::Let)) {
             self.let_declaration();
+        } else if self.try_consume(&TokenKind::Keyword(Keyword::Import)) {
+            self.import_declaration();
         } else {
             self.statement();
         }
@@ -108,6 +126,53 @@ impl<'src> Compiler<'src> {
         self.emit_bytes(&vec![Opcode::DefineGlobal as u8, global]);
     }
 
+    /// Compiles `import "path";`, which binds the module to a global named after its file, or
+    /// `import name from "path";`.
+    fn import_declaration(&mut self) {
+        if self.locals.in_scope() {
+            self.previous_error("Can only import at the top level.");
+        }
+
+        let mut name = None;
+        if self.try_consume(&TokenKind::Identifier) {
+            let token = self.parser.previous.as_ref().unwrap();
+            name = Some(self.source.get_lexeme(token).to_owned());
+            if !self.at_contextual_keyword("from") {
+                self.do_error("Expected 'from' after module name.");
+                return;
+            }
+            self.advance();
+        }
+
+        self.consume(&TokenKind::String, "Expected module path after 'import'.");
+        let path = match self.string_literal() {
+            Some(Value::Obj(Obj::String(path))) => path,
+            _ => return,
+        };
+        let name = match name.or_else(|| module_name(&path)) {
+            Some(name) => name,
+            None => {
+                self.previous_error("Can't name a module after its path. Use 'import name from'.");
+                return;
+            }
+        };
+
+        let path = Compiler::make_constant(&mut self.chunk, Value::Obj(Obj::String(path)));
+        self.emit_bytes(&[Opcode::Import as u8, path]);
+        let identifier = get_or_insert_string(&name, self.strings);
+        let name = self.make_identifier_constant(identifier);
+        self.emit_bytes(&[Opcode::DefineGlobal as u8, name]);
+
+        self.consume(&TokenKind::Semicolon, "Expected ';' after import.");
+    }
+
+    /// Whether the current token is the identifier `word`, which only means something special
+    /// in certain places.
+    fn at_contextual_keyword(&self, word: &str) -> bool {
+        let token = self.parser.current.as_ref().unwrap();
+        token.ty == TokenKind::Identifier && self.source.get_lexeme(token) == word
+    }
+
     fn parse_variable(&mut self, error_message: &str) -> u8 {
         self.consume(&TokenKind::Identifier, error_message);
 
@@ -118,7 +183,7 @@ impl<'src> Compiler<'src> {
 
         let token = self.parser.previous.as_ref().unwrap();
         let lexeme = self.source.get_lexeme(token);
-        let identifier = get_or_insert_string(lexeme, &mut self.strings);
+        let identifier = get_or_insert_string(lexeme, self.strings);
         self.make_identifier_constant(identifier)
     }
 
@@ -139,7 +204,7 @@ impl<'src> Compiler<'src> {
     fn statement(&mut self) {
         if self.try_consume(&TokenKind::Keyword(Keyword::Print)) {
             self.print_statement();
-        } else if self.try_consume(&TokenKind::LeftBrace) {
+        } else if !self.at_map_literal() && self.try_consume(&TokenKind::LeftBrace) {
             self.block_statement();
         } else if self.try_consume(&TokenKind::Keyword(Keyword::If)) {
             self.if_statement();
@@ -147,11 +212,36 @@ impl<'src> Compiler<'src> {
             self.while_statement();
         } else if self.try_consume(&TokenKind::Keyword(Keyword::For)) {
             self.for_statement();
+        } else if self.try_consume(&TokenKind::Keyword(Keyword::Switch)) {
+            self.switch_statement();
+        } else if self.try_consume(&TokenKind::Keyword(Keyword::Try)) {
+            self.try_statement();
+        } else if self.try_consume(&TokenKind::Keyword(Keyword::Throw)) {
+            self.throw_statement();
         } else {
             self.expression_statement();
         }
     }
 
+    /// Whether the current `{` opens a map literal rather than a block. A statement can't start
+    /// with a literal followed by `:`, so that's what we look for ahead of it.
+    fn at_map_literal(&self) -> bool {
+        if !self.parser.check(&TokenKind::LeftBrace) {
+            return false;
+        }
+
+        let mut scanner = self.scanner.clone();
+        let key = scanner.scan_token();
+        let is_literal = match key.ty {
+            TokenKind::String | TokenKind::Integer | TokenKind::Number => true,
+            TokenKind::Keyword(keyword) => {
+                matches!(keyword, Keyword::True | Keyword::False | Keyword::Nil)
+            }
+            _ => false,
+        };
+        is_literal && scanner.scan_token().ty == TokenKind::Colon
+    }
+
     fn block_statement(&mut self) {
         self.locals.begin_scope();
         self.block();
@@ -199,7 +289,28 @@ impl<'src> Compiler<'src> {
         self.locals.begin_scope();
 
         self.consume(&TokenKind::LeftParen, "Expected '(' after 'if'");
+        if self.at_for_in() {
+            self.for_in_loop();
+        } else {
+            self.for_loop();
+        }
 
+        let num_pops = self.locals.end_scope();
+        self.pop_locals(num_pops);
+    }
+
+    /// Whether the loop clauses start with `let name in`.
+    fn at_for_in(&self) -> bool {
+        if !self.parser.check(&TokenKind::Keyword(Keyword::Let)) {
+            return false;
+        }
+
+        let mut scanner = self.scanner.clone();
+        scanner.scan_token().ty == TokenKind::Identifier
+            && scanner.scan_token().ty == TokenKind::Keyword(Keyword::In)
+    }
+
+    fn for_loop(&mut self) {
         // Initializer
         if self.try_consume(&TokenKind::Keyword(Keyword::Let)) {
             self.let_declaration();
@@ -242,8 +353,317 @@ impl<'src> Compiler<'src> {
             self.patch_jump(exit_jump);
             self.emit_byte(Opcode::Pop);
         }
+    }
+
+    /// Compiles `let name in sequence) body`. The sequence and the index of the next element
+    /// stay on the stack as hidden locals underneath the loop variable.
+    fn for_in_loop(&mut self) {
+        self.consume(&TokenKind::Keyword(Keyword::Let), "Expected 'let' in for-in loop");
+        self.consume(&TokenKind::Identifier, "Expected loop variable name");
+        let name = self.get_previous().clone();
+        self.consume(&TokenKind::Keyword(Keyword::In), "Expected 'in' after loop variable");
+
+        self.expression();
+        self.consume(&TokenKind::RightParen, "Expected ')' after for clause.");
+
+        self.emit_byte(Opcode::IterInit);
+        let sequence = self.add_hidden_local();
+        self.add_hidden_local();
+
+        let loop_start = self.chunk.code.len();
+        self.emit_bytes(&[Opcode::IterNext as u8, sequence]);
+        let exit_jump = self.emit_jump_offset();
+
+        // The element ITER_NEXT pushes becomes the loop variable, fresh for every iteration
+        self.locals.begin_scope();
+        if self.locals.add(name, &self.source).is_err() {
+            self.previous_error("Too many local variables in current function");
+        }
+        self.locals.mark_initialized();
+        self.statement();
+        let num_pops = self.locals.end_scope();
+        self.pop_locals(num_pops);
+
+        self.emit_loop(loop_start);
+        self.patch_jump(exit_jump);
+    }
+
+    /// Compiles `try {} catch (e) {} finally {}`, where either clause can be left out.
+    ///
+    /// The VM jumps to a handler with the exception pushed on top of the stack. A `finally`
+    /// block runs with the exception and whether to rethrow it underneath as hidden locals, so
+    /// the normal and exceptional paths can share its code.
+    fn try_statement(&mut self) {
+        self.consume(&TokenKind::LeftBrace, "Expected '{' after 'try'.");
+        let try_handler = self.emit_jump(Opcode::PushHandler);
+        self.block_statement();
+        self.emit_byte(Opcode::PopHandler);
+        let mut exits = vec![self.emit_jump(Opcode::JMP)];
+        self.patch_jump(try_handler);
+
+        let has_catch = self.try_consume(&TokenKind::Keyword(Keyword::Catch));
+        if has_catch {
+            self.consume(&TokenKind::LeftParen, "Expected '(' after 'catch'.");
+            self.consume(&TokenKind::Identifier, "Expected exception variable name.");
+            let name = self.get_previous().clone();
+            let lexeme = self.source.get_lexeme(&name).to_owned();
+            self.consume(
+                &TokenKind::RightParen,
+                "Expected ')' after exception variable.",
+            );
+            self.consume(&TokenKind::LeftBrace, "Expected '{' after catch clause.");
+
+            // An exception escaping the catch block has to run the finally block, if there is
+            // one. We only know once the block has been compiled, so the handler is always
+            // pushed and where it goes is decided afterwards.
+            let catch_handler = self.emit_jump(Opcode::PushHandler);
+
+            // The exception the VM pushed becomes the catch variable
+            self.locals.begin_scope();
+            if self.locals.add(name, &self.source).is_err() {
+                self.previous_error("Too many local variables in current function");
+            }
+            self.locals.mark_initialized();
+            let slot = self.locals.resolve(&lexeme, &self.source).unwrap_or(0);
+            self.block_statement();
+            let num_pops = self.locals.end_scope();
+            self.pop_locals(num_pops);
+
+            self.emit_byte(Opcode::PopHandler);
+            exits.push(self.emit_jump(Opcode::JMP));
+            self.patch_jump(catch_handler);
+            if self.parser.check(&TokenKind::Keyword(Keyword::Finally)) {
+                // The old exception is still in the catch variable's slot, so replace it
+                self.emit_bytes(&[Opcode::SetLocal as u8, slot]);
+                self.emit_byte(Opcode::Pop);
+            } else {
+                // Nothing left to run, so pass the new exception on
+                self.emit_byte(Opcode::Throw);
+            }
+        }
+
+        if self.try_consume(&TokenKind::Keyword(Keyword::Finally)) {
+            self.consume(&TokenKind::LeftBrace, "Expected '{' after 'finally'.");
+            self.emit_byte(Opcode::True);
+            let body_jump = self.emit_jump(Opcode::JMP);
+            for exit in exits {
+                self.patch_jump(exit);
+            }
+            self.emit_bytes(&[Opcode::Nil, Opcode::False]);
+            self.patch_jump(body_jump);
+
+            self.locals.begin_scope();
+            let exception = self.add_hidden_local();
+            let rethrow = self.add_hidden_local();
+            self.block_statement();
+
+            self.emit_bytes(&[Opcode::GetLocal as u8, rethrow]);
+            let skip_jump = self.emit_jump(Opcode::JZ);
+            self.emit_byte(Opcode::Pop);
+            self.emit_bytes(&[Opcode::GetLocal as u8, exception]);
+            self.emit_byte(Opcode::Throw);
+            self.patch_jump(skip_jump);
+            self.emit_byte(Opcode::Pop);
+            let num_pops = self.locals.end_scope();
+            self.pop_locals(num_pops);
+        } else if has_catch {
+            for exit in exits {
+                self.patch_jump(exit);
+            }
+        } else {
+            self.do_error("Expected 'catch' or 'finally' after try block.");
+        }
+    }
+
+    fn throw_statement(&mut self) {
+        self.expression();
+        self.consume(&TokenKind::Semicolon, "Expected ';' after thrown value.");
+        self.emit_byte(Opcode::Throw);
+    }
+
+    fn add_hidden_local(&mut self) -> u8 {
+        match self.locals.add_hidden() {
+            Ok(slot) => slot,
+            Err(message) => {
+                self.do_error(message);
+                0
+            }
+        }
+    }
+
+    fn switch_statement(&mut self) {
+        self.consume(&TokenKind::LeftParen, "Expected '(' after 'switch'");
+        self.expression();
+        self.consume(&TokenKind::RightParen, "Expected ')' after 'switch' value");
+        self.consume(&TokenKind::LeftBrace, "Expected '{' before 'switch' body");
+
+        // The value being switched on stays on the stack as a hidden local, so that case bodies
+        // can declare locals of their own without shifting its slot.
+        self.locals.begin_scope();
+        let subject = self.add_hidden_local();
+
+        // Bodies are emitted first, in source order. The dispatch code only gets emitted once
+        // every case label is known, which is what lets us choose a jump table when it fits.
+        let dispatch_jump = self.emit_jump(Opcode::JMP);
+
+        let mut cases: Vec<(Value, usize)> = Vec::new();
+        let mut default = None;
+        let mut end_jumps = Vec::new();
+
+        while !self.parser.check(&TokenKind::RightBrace) && !self.parser.check(&TokenKind::EOF) {
+            if self.try_consume(&TokenKind::Keyword(Keyword::Case)) {
+                let label = self.case_label();
+                self.consume(&TokenKind::Colon, "Expected ':' after case value");
+
+                if let Some(label) = label {
+                    if cases.iter().any(|(value, _)| *value == label) {
+                        self.do_error("Duplicate case value in switch");
+                    }
+                    cases.push((label, self.chunk.code.len()));
+                }
+            } else if self.try_consume(&TokenKind::Keyword(Keyword::Default)) {
+                self.consume(&TokenKind::Colon, "Expected ':' after 'default'");
+
+                if default.is_some() {
+                    self.do_error("Multiple 'default' labels in switch");
+                }
+                default = Some(self.chunk.code.len());
+            } else {
+                self.do_error("Expected 'case' or 'default' in switch body");
+                break;
+            }
+
+            // Labels directly followed by another label share the next body. The last label always
+            // gets its jump, or an empty body would leave it pointing at the dispatch code.
+            let has_body = self.case_body();
+            if has_body || self.parser.check(&TokenKind::RightBrace) {
+                end_jumps.push(self.emit_jump(Opcode::JMP));
+            }
+        }
+
+        self.consume(&TokenKind::RightBrace, "Expected '}' after 'switch' body");
+
+        self.patch_jump(dispatch_jump);
+        if !self.emit_jump_table(subject, &cases) {
+            self.emit_case_chain(subject, &cases);
+        }
+        if let Some(default) = default {
+            self.emit_loop(default);
+        }
+
+        for end_jump in end_jumps {
+            self.patch_jump(end_jump);
+        }
+
+        let num_pops = self.locals.end_scope();
+        self.pop_locals(num_pops);
+    }
+
+    fn case_label(&mut self) -> Option<Value> {
+        self.advance();
+
+        match self.get_previous().ty.clone() {
+            TokenKind::Integer | TokenKind::Number => self.number_literal(),
+            TokenKind::Minus
+                if self.try_consume(&TokenKind::Integer) || self.try_consume(&TokenKind::Number) =>
+            {
+                self.number_literal().map(|value| match value {
+                    Value::Int(num) => Value::Int(-num),
+                    Value::Number(num) => Value::Number(-num),
+                    value => value,
+                })
+            }
+            TokenKind::String => self.string_literal(),
+            TokenKind::Keyword(Keyword::True) => Some(Value::Bool(true)),
+            TokenKind::Keyword(Keyword::False) => Some(Value::Bool(false)),
+            TokenKind::Keyword(Keyword::Nil) => Some(Value::Nil),
+            _ => {
+                self.do_error("Case value must be a constant literal");
+                None
+            }
+        }
+    }
+
+    /// Compiles the statements of one case, returning whether there were any.
+    fn case_body(&mut self) -> bool {
+        let body_start = self.chunk.code.len();
+
+        self.locals.begin_scope();
+        while !self.parser.check(&TokenKind::Keyword(Keyword::Case))
+            && !self.parser.check(&TokenKind::Keyword(Keyword::Default))
+            && !self.parser.check(&TokenKind::RightBrace)
+            && !self.parser.check(&TokenKind::EOF)
+        {
+            self.declaration();
+        }
+        let num_pops = self.locals.end_scope();
+        self.pop_locals(num_pops);
+
+        self.chunk.code.len() != body_start
+    }
+
+    fn emit_case_chain(&mut self, subject: u8, cases: &[(Value, usize)]) {
+        for (label, body_start) in cases {
+            self.emit_bytes(&[Opcode::GetLocal as u8, subject]);
+            self.emit_constant(label.clone());
+            self.emit_byte(Opcode::Eq);
+
+            let next_case = self.emit_jump(Opcode::JZ);
+            self.emit_byte(Opcode::Pop);
+            self.emit_loop(*body_start);
+
+            self.patch_jump(next_case);
+            self.emit_byte(Opcode::Pop);
+        }
+    }
+
+    /// Emits a `JumpTable` dispatch if every label is an integer and the labels are dense enough,
+    /// returning whether it did.
+    fn emit_jump_table(&mut self, subject: u8, cases: &[(Value, usize)]) -> bool {
+        const MIN_CASES: usize = 4;
+
+        if cases.len() < MIN_CASES {
+            return false;
+        }
+
+        let mut labels = Vec::with_capacity(cases.len());
+        for (label, _) in cases {
+            match label {
+                Value::Int(num) => labels.push(*num),
+                _ => return false,
+            }
+        }
+
+        let min = *labels.iter().min().unwrap();
+        let max = *labels.iter().max().unwrap();
+        let span = max.saturating_sub(min).saturating_add(1);
+        if span > u8::MAX as i64 || span > 2 * cases.len() as i64 {
+            return false;
+        }
+        let span = span as usize;
+
+        self.emit_bytes(&[Opcode::GetLocal as u8, subject]);
+        let min_constant = Compiler::make_constant(&mut self.chunk, Value::Int(min));
+        self.emit_bytes(&[Opcode::JumpTable as u8, min_constant, span as u8]);
+
+        // Entries are backwards offsets from the end of the table, with 0 meaning "no case here"
+        let table_end = self.chunk.code.len() + 2 * span;
+        let mut entries = vec![0u16; span];
+        for (label, (_, body_start)) in labels.iter().zip(cases) {
+            let offset = table_end - body_start;
+            entries[(label - min) as usize] = if let Ok(offset) = offset.try_into() {
+                offset
+            } else {
+                self.do_error(&format!("Offset of {} is too large for jump table", offset));
+                0
+            };
+        }
 
-        self.locals.end_scope();
+        for entry in entries {
+            self.emit_byte((entry >> 8) as u8);
+            self.emit_byte((entry & 0xff) as u8);
+        }
+        true
     }
 
     fn emit_loop(&mut self, loop_start: usize) {
@@ -263,6 +683,11 @@ impl<'src> Compiler<'src> {
 
     fn emit_jump(&mut self, opcode: Opcode) -> usize {
         self.emit_byte(opcode);
+        self.emit_jump_offset()
+    }
+
+    /// Emits a placeholder jump offset for `patch_jump` to fill in.
+    fn emit_jump_offset(&mut self) -> usize {
         self.emit_byte(0xff);
         self.emit_byte(0xff);
         self.chunk.code.len() - 2
@@ -308,14 +733,42 @@ impl<'src> Compiler<'src> {
     }
 
     fn number(&mut self) {
-        let lexeme = self
-            .source
-            .get_lexeme(self.parser.previous.as_ref().unwrap().as_ref());
-        let num = match lexeme.parse::<f64>() {
-            Ok(num) => num,
-            Err(error) => panic!("Tried to parse {} but rip: {}", lexeme, error),
+        if let Some(value) = self.number_literal() {
+            self.emit_constant(value);
+        }
+    }
+
+    fn number_literal(&mut self) -> Option<Value> {
+        let lexeme = self.source.get_lexeme(self.get_previous()).replace('_', "");
+
+        let value = if self.get_previous().ty == TokenKind::Integer {
+            Compiler::parse_integer(&lexeme)
+                .map(Value::Int)
+                .ok_or("Integer literal is too large.")
+        } else {
+            lexeme
+                .parse::<f64>()
+                .map(Value::Number)
+                .map_err(|_| "Invalid number literal.")
         };
-        self.emit_constant(Value::Number(num));
+
+        match value {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.do_error(message);
+                None
+            }
+        }
+    }
+
+    fn parse_integer(lexeme: &str) -> Option<i64> {
+        let radix = match lexeme.get(..2) {
+            Some("0x") | Some("0X") => 16,
+            Some("0o") | Some("0O") => 8,
+            Some("0b") | Some("0B") => 2,
+            _ => return lexeme.parse().ok(),
+        };
+        i64::from_str_radix(&lexeme[2..], radix).ok()
     }
 
     fn literal(&mut self) {
@@ -328,11 +781,47 @@ impl<'src> Compiler<'src> {
     }
 
     fn string(&mut self) {
-        let string = self
+        if let Some(value) = self.string_literal() {
+            self.emit_constant(value);
+        }
+    }
+
+    fn string_literal(&mut self) -> Option<Value> {
+        let raw = self
             .source
             .get_string(self.parser.previous.as_ref().unwrap());
-        let owned_string = get_or_insert_string(string, &mut self.strings);
-        self.emit_constant(Value::Obj(Obj::String(owned_string)))
+        match unescape(raw) {
+            Ok(string) => {
+                let owned_string = get_or_insert_string(&string, self.strings);
+                Some(Value::Obj(Obj::String(owned_string)))
+            }
+            Err(message) => {
+                self.do_error(&message);
+                None
+            }
+        }
+    }
+
+    /// Compiles `"a${x}b${y}c"` to `"a" + x + "b" + y + "c"`, stringifying each expression.
+    fn interpolation(&mut self) {
+        self.string();
+
+        loop {
+            self.expression();
+            self.emit_bytes(&[Opcode::Stringify, Opcode::Add]);
+
+            if self.try_consume(&TokenKind::Interpolation) {
+                self.string();
+                self.emit_byte(Opcode::Add);
+            } else if self.try_consume(&TokenKind::String) {
+                self.string();
+                self.emit_byte(Opcode::Add);
+                return;
+            } else {
+                self.do_error("Expected '}' after interpolated expression.");
+                return;
+            }
+        }
     }
 
     fn and(&mut self) {
@@ -354,20 +843,83 @@ impl<'src> Compiler<'src> {
     }
 
     fn variable(&mut self, can_assign: bool) {
+        let (get_op, set_op, offset) = self.resolve_variable();
+
+        if can_assign && self.try_consume(&TokenKind::Equal) {
+            self.expression();
+            self.emit_bytes(&[set_op as u8, offset])
+        } else if let Some(operator) = self.compound_assignment(can_assign) {
+            self.emit_bytes(&[get_op as u8, offset]);
+            self.expression();
+            self.emit_byte(operator);
+            self.emit_bytes(&[set_op as u8, offset])
+        } else if let Some(operator) = self.increment() {
+            // Postfix: the old value is left on the stack underneath the updated one
+            self.emit_bytes(&[get_op as u8, offset, get_op as u8, offset]);
+            self.emit_constant(Value::Int(1));
+            self.emit_byte(operator);
+            self.emit_bytes(&[set_op as u8, offset, Opcode::Pop as u8]);
+        } else {
+            self.emit_bytes(&[get_op as u8, offset]);
+        }
+    }
+
+    fn prefix_increment(&mut self) {
+        let operator = match self.get_previous().ty {
+            TokenKind::PlusPlus => Opcode::Add,
+            TokenKind::MinusMinus => Opcode::Sub,
+            _ => unreachable!(),
+        };
+
+        if !self.try_consume(&TokenKind::Identifier) {
+            self.do_error("Expected variable name after increment operator.");
+            return;
+        }
+        let (get_op, set_op, offset) = self.resolve_variable();
+
+        self.emit_bytes(&[get_op as u8, offset]);
+        self.emit_constant(Value::Int(1));
+        self.emit_byte(operator);
+        self.emit_bytes(&[set_op as u8, offset]);
+    }
+
+    /// Returns the get and set opcodes and operand for the identifier that was just consumed.
+    fn resolve_variable(&mut self) -> (Opcode, Opcode, u8) {
         let token = self.parser.previous.as_ref().unwrap();
         let lexeme = self.source.get_lexeme(token);
-        let identifier = get_or_insert_string(lexeme, &mut self.strings);
+        let identifier = get_or_insert_string(lexeme, self.strings);
 
-        let (get_op, set_op, offset) = match self.resolve_local(identifier.as_ref()) {
+        match self.resolve_local(identifier.as_ref()) {
             Some(index) => (Opcode::GetLocal, Opcode::SetLocal, index),
             None => (Opcode::GetGlobal, Opcode::SetGlobal, self.make_identifier_constant(identifier))
+        }
+    }
+
+    /// Consumes a compound assignment operator if there is one, returning the opcode it applies.
+    fn compound_assignment(&mut self, can_assign: bool) -> Option<Opcode> {
+        if !can_assign {
+            return None;
+        }
+
+        let operator = match self.get_current().ty {
+            TokenKind::PlusEqual => Opcode::Add,
+            TokenKind::MinusEqual => Opcode::Sub,
+            TokenKind::StarEqual => Opcode::Mul,
+            TokenKind::SlashEqual => Opcode::Div,
+            _ => return None,
         };
+        self.advance();
+        Some(operator)
+    }
 
-        if can_assign && self.try_consume(&TokenKind::Equal) {
-            self.expression();
-            self.emit_bytes(&vec![set_op as u8, offset])
+    /// Consumes a postfix `++` or `--` if there is one, returning the opcode it applies.
+    fn increment(&mut self) -> Option<Opcode> {
+        if self.try_consume(&TokenKind::PlusPlus) {
+            Some(Opcode::Add)
+        } else if self.try_consume(&TokenKind::MinusMinus) {
+            Some(Opcode::Sub)
         } else {
-            self.emit_bytes(&vec![get_op as u8, offset]);
+            None
         }
     }
 
@@ -381,11 +933,105 @@ impl<'src> Compiler<'src> {
 
     fn grouping(&mut self) {
         self.expression();
-        self.parser.consume(
-            &mut self.scanner,
-            &TokenKind::RightParen,
-            "Expected '(' after expression.",
-        );
+        self.consume(&TokenKind::RightParen, "Expected '(' after expression.");
+    }
+
+    fn list(&mut self) {
+        let mut count = 0;
+        while !self.parser.check(&TokenKind::RightBracket) && !self.parser.check(&TokenKind::EOF) {
+            self.expression();
+            count += 1;
+            if !self.try_consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightBracket, "Expected ']' after list elements.");
+
+        if count > u8::MAX as usize {
+            self.previous_error("Can't have more than 255 elements in a list literal.");
+        }
+        self.emit_bytes(&[Opcode::BuildList as u8, count as u8]);
+    }
+
+    fn map(&mut self) {
+        let mut count = 0;
+        while !self.parser.check(&TokenKind::RightBrace) && !self.parser.check(&TokenKind::EOF) {
+            self.expression();
+            self.consume(&TokenKind::Colon, "Expected ':' after map key.");
+            self.expression();
+            count += 1;
+            if !self.try_consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightBrace, "Expected '}' after map entries.");
+
+        if count > u8::MAX as usize {
+            self.previous_error("Can't have more than 255 entries in a map literal.");
+        }
+        self.emit_bytes(&[Opcode::BuildMap as u8, count as u8]);
+    }
+
+    /// Compiles `[index]`, `[start:end]` or `[index] = value` after an expression. Either bound
+    /// of a slice can be left out.
+    fn index(&mut self, can_assign: bool) {
+        let is_slice = if self.try_consume(&TokenKind::Colon) {
+            self.emit_byte(Opcode::Nil);
+            true
+        } else {
+            self.expression();
+            self.try_consume(&TokenKind::Colon)
+        };
+
+        if is_slice {
+            if self.parser.check(&TokenKind::RightBracket) {
+                self.emit_byte(Opcode::Nil);
+            } else {
+                self.expression();
+            }
+            self.consume(&TokenKind::RightBracket, "Expected ']' after slice.");
+            self.emit_byte(Opcode::GetSlice);
+            return;
+        }
+        self.consume(&TokenKind::RightBracket, "Expected ']' after index.");
+
+        if can_assign && self.try_consume(&TokenKind::Equal) {
+            self.expression();
+            self.emit_byte(Opcode::SetIndex);
+        } else {
+            self.emit_byte(Opcode::GetIndex);
+        }
+    }
+
+    fn call(&mut self) {
+        let arg_count = self.argument_list();
+        self.emit_bytes(&[Opcode::Call as u8, arg_count]);
+    }
+
+    fn dot(&mut self) {
+        self.consume(&TokenKind::Identifier, "Expected property name after '.'.");
+        let token = self.parser.previous.as_ref().unwrap();
+        let lexeme = self.source.get_lexeme(token);
+        let identifier = get_or_insert_string(lexeme, self.strings);
+        let name = self.make_identifier_constant(identifier);
+        self.emit_bytes(&[Opcode::GetProperty as u8, name]);
+    }
+
+    fn argument_list(&mut self) -> u8 {
+        let mut count = 0;
+        while !self.parser.check(&TokenKind::RightParen) && !self.parser.check(&TokenKind::EOF) {
+            self.expression();
+            count += 1;
+            if !self.try_consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightParen, "Expected ')' after arguments.");
+
+        if count > u8::MAX as usize {
+            self.previous_error("Can't have more than 255 arguments.");
+        }
+        count as u8
     }
 
     fn unary(&mut self) {
@@ -396,6 +1042,7 @@ impl<'src> Compiler<'src> {
         match operator {
             TokenKind::Minus => self.emit_byte(Opcode::Neg),
             TokenKind::Bang => self.emit_byte(Opcode::Not),
+            TokenKind::Tilde => self.emit_byte(Opcode::BitNot),
             _ => unreachable!(),
         }
     }
@@ -404,19 +1051,33 @@ impl<'src> Compiler<'src> {
         let operator = self.parser.previous.as_ref().unwrap().ty.clone();
 
         let precedence = self.get_infix_rule(&operator).precedence;
-        self.parse_precendence((precedence as u32 + 1).into());
+        if operator == TokenKind::StarStar {
+            // Exponentiation is right associative
+            self.parse_precendence(precedence);
+        } else {
+            self.parse_precendence((precedence as u32 + 1).into());
+        }
 
         match operator {
             TokenKind::Plus => self.emit_byte(Opcode::Add),
             TokenKind::Minus => self.emit_byte(Opcode::Sub),
             TokenKind::Star => self.emit_byte(Opcode::Mul),
             TokenKind::Slash => self.emit_byte(Opcode::Div),
-            TokenKind::BangEqual => self.emit_bytes(&vec![Opcode::Eq, Opcode::Not]),
+            TokenKind::Percent => self.emit_byte(Opcode::Mod),
+            TokenKind::StarStar => self.emit_byte(Opcode::Pow),
+            TokenKind::TildeSlash => self.emit_byte(Opcode::FloorDiv),
+            TokenKind::Ampersand => self.emit_byte(Opcode::BitAnd),
+            TokenKind::Pipe => self.emit_byte(Opcode::BitOr),
+            TokenKind::Caret => self.emit_byte(Opcode::BitXor),
+            TokenKind::LessLess => self.emit_byte(Opcode::Shl),
+            TokenKind::GreaterGreater => self.emit_byte(Opcode::Shr),
+            TokenKind::BangEqual => self.emit_byte(Opcode::Ne),
             TokenKind::EqualEqual => self.emit_byte(Opcode::Eq),
-            TokenKind::GreaterEqual => self.emit_bytes(&vec![Opcode::Lt, Opcode::Not]),
+            TokenKind::GreaterEqual => self.emit_byte(Opcode::Ge),
             TokenKind::Greater => self.emit_byte(Opcode::Gt),
-            TokenKind::LessEqual => self.emit_bytes(&vec![Opcode::Gt, Opcode::Not]),
+            TokenKind::LessEqual => self.emit_byte(Opcode::Le),
             TokenKind::Less => self.emit_byte(Opcode::Lt),
+            TokenKind::DotDot => self.emit_byte(Opcode::BuildRange),
             _ => unreachable!(),
         };
     }
@@ -445,12 +1106,40 @@ impl<'src> Compiler<'src> {
         Some(Box::new(|s: &mut Compiler, _| s.string()))
     }
 
+    fn get_interpolation<'a>() -> Option<ParseFn<'a>> {
+        Some(Box::new(|s: &mut Compiler, _| s.interpolation()))
+    }
+
     fn get_variable<'a>() -> Option<ParseFn<'a>> {
         Some(Box::new(|s: &mut Compiler, can_assign| {
             s.variable(can_assign)
         }))
     }
 
+    fn get_prefix_increment<'a>() -> Option<ParseFn<'a>> {
+        Some(Box::new(|s: &mut Compiler, _| s.prefix_increment()))
+    }
+
+    fn get_list<'a>() -> Option<ParseFn<'a>> {
+        Some(Box::new(|s: &mut Compiler, _| s.list()))
+    }
+
+    fn get_map<'a>() -> Option<ParseFn<'a>> {
+        Some(Box::new(|s: &mut Compiler, _| s.map()))
+    }
+
+    fn get_index<'a>() -> Option<ParseFn<'a>> {
+        Some(Box::new(|s: &mut Compiler, can_assign| s.index(can_assign)))
+    }
+
+    fn get_call<'a>() -> Option<ParseFn<'a>> {
+        Some(Box::new(|s: &mut Compiler, _| s.call()))
+    }
+
+    fn get_dot<'a>() -> Option<ParseFn<'a>> {
+        Some(Box::new(|s: &mut Compiler, _| s.dot()))
+    }
+
     fn get_and<'a>() -> Option<ParseFn<'a>> {
         Some(Box::new(|s: &mut Compiler, _| s.and()))
     }
@@ -465,10 +1154,13 @@ impl<'src> Compiler<'src> {
         match token_kind {
             LeftParen => ParseRule::new(Compiler::get_grouping(), Precedence::Call),
             RightParen => ParseRule::new(None, Precedence::None),
-            LeftBrace => ParseRule::new(None, Precedence::None),
+            LeftBrace => ParseRule::new(Compiler::get_map(), Precedence::None),
             RightBrace => ParseRule::new(None, Precedence::None),
+            LeftBracket => ParseRule::new(Compiler::get_list(), Precedence::None),
+            RightBracket => ParseRule::new(None, Precedence::None),
             Comma => ParseRule::new(None, Precedence::None),
             Dot => ParseRule::new(None, Precedence::Call),
+            DotDot => ParseRule::new(None, Precedence::Range),
             Minus => ParseRule::new(Compiler::get_unary(), Precedence::Term),
             Plus => ParseRule::new(None, Precedence::Term),
             Semicolon => ParseRule::new(None, Precedence::None),
@@ -482,11 +1174,24 @@ impl<'src> Compiler<'src> {
             LessEqual => ParseRule::new(None, Precedence::Comparison),
             Greater => ParseRule::new(None, Precedence::Comparison),
             GreaterEqual => ParseRule::new(None, Precedence::Comparison),
+            PlusEqual | MinusEqual | StarEqual | SlashEqual => {
+                ParseRule::new(None, Precedence::None)
+            }
+            PlusPlus | MinusMinus => ParseRule::new(Compiler::get_prefix_increment(), Precedence::None),
             Identifier => ParseRule::new(Compiler::get_variable(), Precedence::None),
             String => ParseRule::new(Compiler::get_string(), Precedence::None),
-            Number => ParseRule::new(Compiler::get_number(), Precedence::None),
+            Interpolation => ParseRule::new(Compiler::get_interpolation(), Precedence::None),
+            Integer | Number => ParseRule::new(Compiler::get_number(), Precedence::None),
             QuestionMark => ParseRule::new(None, Precedence::None),
             Colon => ParseRule::new(None, Precedence::None),
+            Percent => ParseRule::new(None, Precedence::Factor),
+            StarStar => ParseRule::new(None, Precedence::Exponent),
+            TildeSlash => ParseRule::new(None, Precedence::Factor),
+            Ampersand => ParseRule::new(None, Precedence::BitAnd),
+            Pipe => ParseRule::new(None, Precedence::BitOr),
+            Caret => ParseRule::new(None, Precedence::BitXor),
+            Tilde => ParseRule::new(Compiler::get_unary(), Precedence::None),
+            LessLess | GreaterGreater => ParseRule::new(None, Precedence::Shift),
             Keyword(keyword) => match keyword {
                 And => ParseRule::new(None, Precedence::And),
                 Class => ParseRule::new(None, Precedence::None),
@@ -504,6 +1209,12 @@ impl<'src> Compiler<'src> {
                 True => ParseRule::new(Compiler::get_literal(), Precedence::None),
                 Let => ParseRule::new(None, Precedence::None),
                 While => ParseRule::new(None, Precedence::None),
+                Switch => ParseRule::new(None, Precedence::None),
+                Case => ParseRule::new(None, Precedence::None),
+                Default => ParseRule::new(None, Precedence::None),
+                In => ParseRule::new(None, Precedence::None),
+                Import => ParseRule::new(None, Precedence::None),
+                Try | Catch | Finally | Throw => ParseRule::new(None, Precedence::None),
             },
             Error(_) => ParseRule::new(None, Precedence::None),
             EOF => ParseRule::new(None, Precedence::None),
@@ -514,12 +1225,15 @@ impl<'src> Compiler<'src> {
         use super::Keyword::*;
         use TokenKind::*;
         match token_kind {
-            LeftParen => ParseRule::new(None, Precedence::Call),
+            LeftParen => ParseRule::new(Compiler::get_call(), Precedence::Call),
             RightParen => ParseRule::new(None, Precedence::None),
             LeftBrace => ParseRule::new(None, Precedence::None),
             RightBrace => ParseRule::new(None, Precedence::None),
+            LeftBracket => ParseRule::new(Compiler::get_index(), Precedence::Call),
+            RightBracket => ParseRule::new(None, Precedence::None),
             Comma => ParseRule::new(None, Precedence::None),
-            Dot => ParseRule::new(None, Precedence::Call),
+            Dot => ParseRule::new(Compiler::get_dot(), Precedence::Call),
+            DotDot => ParseRule::new(Compiler::get_binary(), Precedence::Range),
             Minus => ParseRule::new(Compiler::get_binary(), Precedence::Term),
             Plus => ParseRule::new(Compiler::get_binary(), Precedence::Term),
             Semicolon => ParseRule::new(None, Precedence::None),
@@ -533,11 +1247,24 @@ impl<'src> Compiler<'src> {
             LessEqual => ParseRule::new(Compiler::get_binary(), Precedence::Comparison),
             Greater => ParseRule::new(Compiler::get_binary(), Precedence::Comparison),
             GreaterEqual => ParseRule::new(Compiler::get_binary(), Precedence::Comparison),
+            PlusEqual | MinusEqual | StarEqual | SlashEqual => {
+                ParseRule::new(None, Precedence::None)
+            }
+            PlusPlus | MinusMinus => ParseRule::new(None, Precedence::None),
             Identifier => ParseRule::new(None, Precedence::None),
             String => ParseRule::new(None, Precedence::None),
-            Number => ParseRule::new(Compiler::get_number(), Precedence::None),
+            Interpolation => ParseRule::new(None, Precedence::None),
+            Integer | Number => ParseRule::new(Compiler::get_number(), Precedence::None),
             QuestionMark => ParseRule::new(None, Precedence::None),
             Colon => ParseRule::new(None, Precedence::None),
+            Percent => ParseRule::new(Compiler::get_binary(), Precedence::Factor),
+            StarStar => ParseRule::new(Compiler::get_binary(), Precedence::Exponent),
+            TildeSlash => ParseRule::new(Compiler::get_binary(), Precedence::Factor),
+            Ampersand => ParseRule::new(Compiler::get_binary(), Precedence::BitAnd),
+            Pipe => ParseRule::new(Compiler::get_binary(), Precedence::BitOr),
+            Caret => ParseRule::new(Compiler::get_binary(), Precedence::BitXor),
+            Tilde => ParseRule::new(None, Precedence::None),
+            LessLess | GreaterGreater => ParseRule::new(Compiler::get_binary(), Precedence::Shift),
             Keyword(keyword) => match keyword {
                 And => ParseRule::new(Compiler::get_and(), Precedence::And),
                 Class => ParseRule::new(None, Precedence::None),
@@ -555,6 +1282,12 @@ impl<'src> Compiler<'src> {
                 True => ParseRule::new(None, Precedence::None),
                 Let => ParseRule::new(None, Precedence::None),
                 While => ParseRule::new(None, Precedence::None),
+                Switch => ParseRule::new(None, Precedence::None),
+                Case => ParseRule::new(None, Precedence::None),
+                Default => ParseRule::new(None, Precedence::None),
+                In => ParseRule::new(None, Precedence::None),
+                Import => ParseRule::new(None, Precedence::None),
+                Try | Catch | Finally | Throw => ParseRule::new(None, Precedence::None),
             },
             Error(_) => ParseRule::new(None, Precedence::None),
             EOF => ParseRule::new(None, Precedence::None),
@@ -572,6 +1305,7 @@ impl<'src> Compiler<'src> {
         if let Some(prefix_rule) = self.get_prefix_rule(&self.get_previous().ty).function {
             prefix_rule(self, can_assign);
         } else {
+            self.previous_error("Expected expression.");
             return;
         };
 
@@ -579,13 +1313,15 @@ impl<'src> Compiler<'src> {
         while precedence <= other_precedence {
             self.advance();
             if let Some(infix_rule) = self.get_infix_rule(&self.get_previous().ty).function {
-                infix_rule(self, false);
+                infix_rule(self, can_assign);
             }
 
             other_precedence = self.get_infix_rule(&self.get_current().ty).precedence;
         }
 
-        if can_assign && self.try_consume(&TokenKind::Equal) {
+        if can_assign
+            && (self.try_consume(&TokenKind::Equal) || self.compound_assignment(true).is_some())
+        {
             self.do_error("Invalid assignment target.");
             self.expression();
         }
@@ -652,25 +1388,43 @@ impl<'src> Compiler<'src> {
             .unwrap()
             .ty
             .try_into_error()
-            .unwrap();
+            .unwrap()
+            .to_owned();
         let token = self.parser.current.as_ref().unwrap().clone();
-
-        let lexeme = self.source.get_lexeme(&token);
-        Compiler::error(lexeme, &mut self.had_error, &token, error_message)
+        self.error(&token, &error_message)
     }
 
     fn do_error(&mut self, error_message: &str) {
         let token = self.parser.current.as_ref().unwrap().clone();
-        let lexeme = self.source.get_lexeme(&token);
-        Compiler::error(lexeme, &mut self.had_error, &token, error_message)
+        self.error_at(&token, error_message);
     }
 
-    fn error(lexeme: &str, error: &mut bool, token: &Token, message: &str) {
-        PrettyPrinter::new(String::new())
-            .parse_error(token, lexeme, message)
-            .newline()
-            .print();
-        *error = true;
+    fn previous_error(&mut self, error_message: &str) {
+        let token = self.parser.previous.as_ref().unwrap().clone();
+        self.error_at(&token, error_message);
+    }
+
+    fn error_at(&mut self, token: &Token, error_message: &str) {
+        if self.panic_mode {
+            return;
+        }
+        self.panic_mode = true;
+
+        self.error(token, error_message)
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        let location = match token.ty {
+            TokenKind::EOF => Some("at end".to_owned()),
+            TokenKind::Error(_) => None,
+            _ => Some(format!("at {}", self.source.get_lexeme(token))),
+        };
+        self.errors.push(SyntaxError {
+            line: token.position.line,
+            location,
+            message: message.to_owned(),
+        });
+        self.had_error = true;
     }
 
     fn synchronize(&mut self) {
@@ -689,6 +1443,10 @@ impl<'src> Compiler<'src> {
                     | Keyword::For
                     | Keyword::If
                     | Keyword::While
+                    | Keyword::Switch
+                    | Keyword::Import
+                    | Keyword::Try
+                    | Keyword::Throw
                     | Keyword::Print
                     | Keyword::Return => return,
                     _ => (),
@@ -700,3 +1458,18 @@ impl<'src> Compiler<'src> {
         }
     }
 }
+
+/// The global an imported file is bound to by default: its name without the extension, as long
+/// as that's a valid identifier.
+fn module_name(path: &str) -> Option<String> {
+    let stem = Path::new(path).file_stem()?.to_str()?;
+    let mut chars = stem.chars();
+    let first = chars.next()?;
+    let valid = (first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if valid {
+        Some(stem.to_owned())
+    } else {
+        None
+    }
+}
@@ -1 +1,40 @@
-pub struct CompileError {}
+use std::error::Error;
+use std::fmt;
+
+/// A problem found at one token while compiling.
+#[derive(Clone, Debug)]
+pub struct SyntaxError {
+    pub line: usize,
+    /// Where on the line, like "at end", when the token can be shown
+    pub location: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "[{}] Error {}: {}", self.line, location, self.message),
+            None => write!(f, "[{}] Error: {}", self.line, self.message),
+        }
+    }
+}
+
+/// Every problem found while compiling a source, in the order they were found.
+#[derive(Clone, Debug)]
+pub struct CompileError {
+    pub errors: Vec<SyntaxError>,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for CompileError {}
@@ -1,12 +1,17 @@
 use crate::compiler::{Keyword, Position, Source, Token, TokenKind};
 use std::iter::Peekable;
 use std::str::Chars;
+use unicode_xid::UnicodeXID;
 
+#[derive(Clone)]
 pub struct Scanner<'src> {
     source: Peekable<Chars<'src>>,
+    /// Byte offsets into the source, so they can be used to slice out lexemes
     start: usize,
     current: usize,
     line: usize,
+    /// Brace depth inside each string interpolation we're currently scanning the expression of
+    interpolations: Vec<usize>,
 }
 
 impl<'src> Scanner<'src> {
@@ -16,13 +21,16 @@ impl<'src> Scanner<'src> {
             start: 0,
             current: 0,
             line: 1,
+            interpolations: Vec::new(),
         }
     }
 
     pub fn scan_token(&mut self) -> Token {
         use TokenKind::*;
 
-        self.skip_whitespace();
+        if let Err(message) = self.skip_whitespace() {
+            return self.error_token(message);
+        }
 
         self.start = self.current;
 
@@ -33,17 +41,83 @@ impl<'src> Scanner<'src> {
         let token_kind = match self.advance() {
             '(' => LeftParen,
             ')' => RightParen,
-            '{' => LeftBrace,
-            '}' => RightBrace,
+            '[' => LeftBracket,
+            ']' => RightBracket,
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                LeftBrace
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    // End of an interpolated expression, so carry on with the rest of the string
+                    self.interpolations.pop();
+                    self.string()
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    RightBrace
+                }
+                None => RightBrace,
+            },
             ',' => Comma,
-            '.' => Dot,
-            '-' => Minus,
-            '+' => Plus,
+            '.' => {
+                if self.try_consume('.') {
+                    DotDot
+                } else {
+                    Dot
+                }
+            }
             ';' => Semicolon,
-            '*' => Star,
             '?' => QuestionMark,
             ':' => Colon,
 
+            '-' => {
+                if self.try_consume('-') {
+                    MinusMinus
+                } else if self.try_consume('=') {
+                    MinusEqual
+                } else {
+                    Minus
+                }
+            }
+            '+' => {
+                if self.try_consume('+') {
+                    PlusPlus
+                } else if self.try_consume('=') {
+                    PlusEqual
+                } else {
+                    Plus
+                }
+            }
+            '*' => {
+                if self.try_consume('*') {
+                    StarStar
+                } else if self.try_consume('=') {
+                    StarEqual
+                } else {
+                    Star
+                }
+            }
+            '%' => Percent,
+            '&' => Ampersand,
+            '|' => Pipe,
+            '^' => Caret,
+            '~' => {
+                if self.try_consume('/') {
+                    TildeSlash
+                } else {
+                    Tilde
+                }
+            }
+            '/' => {
+                if self.try_consume('=') {
+                    SlashEqual
+                } else {
+                    Slash
+                }
+            }
             '!' => {
                 if self.try_consume('=') {
                     BangEqual
@@ -59,14 +133,18 @@ impl<'src> Scanner<'src> {
                 }
             }
             '<' => {
-                if self.try_consume('=') {
+                if self.try_consume('<') {
+                    LessLess
+                } else if self.try_consume('=') {
                     LessEqual
                 } else {
                     Less
                 }
             }
             '>' => {
-                if self.try_consume('=') {
+                if self.try_consume('>') {
+                    GreaterGreater
+                } else if self.try_consume('=') {
                     GreaterEqual
                 } else {
                     Greater
@@ -75,8 +153,8 @@ impl<'src> Scanner<'src> {
             '"' => self.string(),
             c => {
                 if c.is_digit(10) {
-                    self.number()
-                } else if c.is_ascii_alphanumeric() || c == '_' {
+                    self.number(c)
+                } else if UnicodeXID::is_xid_start(c) || c == '_' {
                     self.identifier(c)
                 } else {
                     self.error_kind("Invalid character")
@@ -103,26 +181,31 @@ impl<'src> Scanner<'src> {
     }
 
     fn is_at_end(&mut self) -> bool {
-        return self.source.peek().is_none();
+        self.source.peek().is_none()
     }
 
+    /// Returns '\0' at the end of the source, which never matches anything the scanner looks for.
     fn peek(&mut self) -> char {
-        *self.source.peek().unwrap()
+        self.source.peek().copied().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
         let mut it = self.source.clone();
-        it.nth(2).unwrap()
+        it.nth(1).unwrap_or('\0')
     }
 
     fn advance(&mut self) -> char {
-        let c = self.source.next().unwrap();
-        self.current += 1;
-        c
+        match self.source.next() {
+            Some(c) => {
+                self.current += c.len_utf8();
+                c
+            }
+            None => '\0',
+        }
     }
 
     fn try_consume(&mut self, c: char) -> bool {
-        if *self.source.peek().unwrap() == c {
+        if !self.is_at_end() && self.peek() == c {
             self.advance();
             true
         } else {
@@ -130,12 +213,23 @@ impl<'src> Scanner<'src> {
         }
     }
 
+    /// Scans up to the closing quote, or up to the `${` of an interpolation. Escape sequences are
+    /// only skipped over here, and get checked when the compiler unescapes the string.
     fn string(&mut self) -> TokenKind {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+        while !self.is_at_end() && self.peek() != '"' {
+            match self.advance() {
+                '\n' => self.line += 1,
+                '\\' if !self.is_at_end() => {
+                    // Skip the escaped character, so that \" doesn't end the string
+                    self.advance();
+                }
+                '$' if !self.is_at_end() && self.peek() == '{' => {
+                    self.advance();
+                    self.interpolations.push(0);
+                    return TokenKind::Interpolation;
+                }
+                _ => (),
             }
-            self.advance();
         }
         if self.is_at_end() {
             return self.error_kind("Unterminated String");
@@ -145,27 +239,116 @@ impl<'src> Scanner<'src> {
         TokenKind::String
     }
 
-    fn number(&mut self) -> TokenKind {
-        while !self.is_at_end() && self.peek().is_digit(10) {
-            self.advance();
+    fn number(&mut self, first: char) -> TokenKind {
+        if first == '0' && !self.is_at_end() {
+            let radix = match self.peek() {
+                'x' | 'X' => Some((16, "hexadecimal")),
+                'o' | 'O' => Some((8, "octal")),
+                'b' | 'B' => Some((2, "binary")),
+                _ => None,
+            };
+            if let Some((radix, name)) = radix {
+                self.advance();
+                return self.radix_number(radix, name);
+            }
+        }
+
+        if let Err(message) = self.digits(10, true) {
+            return self.malformed_number(message);
         }
+        let mut token_kind = TokenKind::Integer;
 
         // Check for fractional
-        if !self.is_at_end() && self.peek() == '.' && self.peek_next().is_digit(10) {
+        if !self.is_at_end() && self.peek() == '.' && self.peek_next().is_ascii_digit() {
             self.advance(); // Consume the .
-            while self.peek().is_digit(10) {
+            if let Err(message) = self.digits(10, false) {
+                return self.malformed_number(message);
+            }
+            token_kind = TokenKind::Number;
+        }
+
+        // Check for exponent
+        if !self.is_at_end() && (self.peek() == 'e' || self.peek() == 'E') {
+            self.advance();
+            if !self.is_at_end() && (self.peek() == '+' || self.peek() == '-') {
                 self.advance();
             }
+            if self.is_at_end() || !self.peek().is_ascii_digit() {
+                return self.malformed_number("Expected digits in exponent");
+            }
+            if let Err(message) = self.digits(10, false) {
+                return self.malformed_number(message);
+            }
+            token_kind = TokenKind::Number;
+        }
+
+        if !self.is_at_end() && (self.peek().is_alphanumeric() || self.peek() == '_') {
+            return self.malformed_number("Invalid character in number literal");
         }
+        token_kind
+    }
 
-        TokenKind::Number
+    fn radix_number(&mut self, radix: u32, name: &str) -> TokenKind {
+        match self.digits(radix, false) {
+            Ok(0) => self.malformed_number(&format!("Expected digits in {} literal", name)),
+            Ok(_) => {
+                if !self.is_at_end() && self.peek().is_alphanumeric() {
+                    let message = format!("Invalid digit '{}' in {} literal", self.peek(), name);
+                    self.malformed_number(&message)
+                } else {
+                    TokenKind::Integer
+                }
+            }
+            Err(message) => self.malformed_number(message),
+        }
+    }
+
+    /// Consumes a run of digits, allowing single underscores between them. Returns the number of
+    /// digits consumed. `after_digit` says whether the run continues a digit that was already
+    /// consumed, such as the first digit of a decimal literal.
+    fn digits(&mut self, radix: u32, after_digit: bool) -> Result<usize, &'static str> {
+        let mut after_digit = after_digit;
+        let mut after_underscore = false;
+        let mut count = 0;
+
+        while !self.is_at_end() {
+            let c = self.peek();
+            if c.is_digit(radix) {
+                after_digit = true;
+                after_underscore = false;
+                count += 1;
+            } else if c == '_' {
+                if !after_digit {
+                    return Err("'_' can only be used between digits");
+                }
+                after_digit = false;
+                after_underscore = true;
+            } else {
+                break;
+            }
+            self.advance();
+        }
+
+        if after_underscore {
+            Err("'_' can only be used between digits")
+        } else {
+            Ok(count)
+        }
+    }
+
+    /// Consumes the rest of a malformed number literal, so the error covers all of it.
+    fn malformed_number(&mut self, message: &str) -> TokenKind {
+        while !self.is_at_end() && (self.peek().is_alphanumeric() || self.peek() == '_') {
+            self.advance();
+        }
+        self.error_kind(message)
     }
 
     fn identifier(&mut self, c: char) -> TokenKind {
         let mut buffer = String::with_capacity(16);
         buffer.push(c);
 
-        while !self.is_at_end() && (self.peek().is_ascii_alphanumeric() || self.peek() == '_') {
+        while !self.is_at_end() && UnicodeXID::is_xid_continue(self.peek()) {
             buffer.push(self.advance());
         }
 
@@ -193,11 +376,20 @@ impl<'src> Scanner<'src> {
             "true" => Some(True),
             "let" => Some(Let),
             "while" => Some(While),
+            "switch" => Some(Switch),
+            "case" => Some(Case),
+            "default" => Some(Default),
+            "in" => Some(In),
+            "import" => Some(Import),
+            "try" => Some(Try),
+            "catch" => Some(Catch),
+            "finally" => Some(Finally),
+            "throw" => Some(Throw),
             _ => None,
         }
     }
 
-    fn skip_whitespace(&mut self) {
+    fn skip_whitespace(&mut self) -> Result<(), &'static str> {
         while !self.is_at_end() {
             match self.peek() {
                 ' ' | '\r' | '\t' => {
@@ -208,28 +400,217 @@ impl<'src> Scanner<'src> {
                     self.advance();
                 }
                 '/' => {
-                    if self.try_consume('/') {
+                    if self.peek_next() == '/' {
                         // // Comment
-                        while self.peek() != '\n' && !self.is_at_end() {
+                        while !self.is_at_end() && self.peek() != '\n' {
                             self.advance();
                         }
-                    } else if self.try_consume('*') {
+                    } else if self.peek_next() == '*' {
                         // /* Comment block
-                        while self.peek() != '*'
-                            && self.peek_next() != '/'
-                            && !self.is_at_end()
-                            && self.peek_next() != '\0'
-                        {
-                            self.advance();
-                        }
+                        self.start = self.current;
                         self.advance();
                         self.advance();
+                        self.block_comment()?;
                     } else {
-                        return;
+                        return Ok(());
                     }
                 }
-                _ => return,
+                _ => return Ok(()),
             };
         }
+        Ok(())
+    }
+
+    /// Skips the rest of a block comment whose opening `/*` has been consumed. Block comments nest.
+    fn block_comment(&mut self) -> Result<(), &'static str> {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                return Err("Unterminated block comment");
+            }
+
+            match self.advance() {
+                '\n' => self.line += 1,
+                '/' if self.peek() == '*' => {
+                    self.advance();
+                    depth += 1;
+                }
+                '*' if self.peek() == '/' => {
+                    self.advance();
+                    depth -= 1;
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the escape sequences in the contents of a string literal with the characters they
+/// stand for.
+pub fn unescape(raw: &str) -> Result<String, String> {
+    let mut string = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('$') => '$',
+            Some('u') => unicode_escape(&mut chars)?,
+            Some(c) => return Err(format!("Invalid escape sequence '\\{}'", c)),
+            None => return Err("Expected escape sequence after '\\'".to_owned()),
+        };
+        string.push(escaped);
+    }
+
+    Ok(string)
+}
+
+/// Parses the `{XXXX}` part of a `\u{XXXX}` escape.
+fn unicode_escape(chars: &mut Chars) -> Result<char, String> {
+    if chars.next() != Some('{') {
+        return Err("Expected '{' after '\\u'".to_owned());
+    }
+
+    let mut digits = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) if c.is_ascii_hexdigit() && digits.len() < 6 => digits.push(c),
+            _ => return Err("Unicode escape must be 1 to 6 hex digits followed by '}'".to_owned()),
+        }
+    }
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(std::char::from_u32)
+        .ok_or_else(|| format!("Invalid unicode escape '\\u{{{}}}'", digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::InternMap;
+    use crate::compiler::Compiler;
+    use crate::stdlib::Rng;
+
+    /// Scans the whole source, returning each token's kind, lexeme and line.
+    fn scan(source: &str) -> Vec<(TokenKind, &str, usize)> {
+        let mut scanner = Scanner::new(Source::new(source));
+        let mut tokens = Vec::new();
+        loop {
+            let token = scanner.scan_token();
+            let lexeme = &source[token.position.start..token.position.end];
+            if token.ty == TokenKind::EOF {
+                return tokens;
+            }
+            tokens.push((token.ty, lexeme, token.position.line));
+        }
+    }
+
+    #[test]
+    fn lexemes_after_multibyte_strings() {
+        use TokenKind::*;
+        let tokens = scan("print \"héllo 😀\" + x;");
+        assert_eq!(
+            tokens,
+            vec![
+                (Keyword(super::Keyword::Print), "print", 1),
+                (String, "\"héllo 😀\"", 1),
+                (Plus, "+", 1),
+                (Identifier, "x", 1),
+                (Semicolon, ";", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_after_multibyte_strings_and_comments() {
+        let source = "// ünïcode comment\n\"a\nß\" /* ☃\n☃ */ y\n// 😀\nz";
+        let tokens = scan(source);
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenKind::String, "\"a\nß\"", 3),
+                (TokenKind::Identifier, "y", 4),
+                (TokenKind::Identifier, "z", 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        use TokenKind::*;
+        let tokens = scan("let café = 1; let 変数 = café;");
+        let lexemes: Vec<_> = tokens.iter().map(|(_, lexeme, _)| *lexeme).collect();
+        assert_eq!(lexemes, vec!["let", "café", "=", "1", ";", "let", "変数", "=", "café", ";"]);
+        assert_eq!(tokens[1].0, Identifier);
+        assert_eq!(tokens[6].0, Identifier);
+    }
+
+    #[test]
+    fn non_xid_character_is_an_error_token() {
+        let tokens = scan("let a = ☃; b");
+        assert_eq!(tokens[3].0.try_into_error(), Some("Invalid character"));
+        assert_eq!(tokens[3].1, "☃");
+        assert_eq!(tokens[4].1, ";");
+        assert_eq!(tokens[5].1, "b");
+    }
+
+    fn below(rng: &mut Rng, n: usize) -> usize {
+        (rng.next_u64() % n as u64) as usize
+    }
+
+    /// Bytes that drive the scanner and compiler down their more interesting paths.
+    const INTERESTING: &[u8] = b"!=<>/*+-~.\"\\${}()[]:;,_0123456789xobeEu\n letifswitchcasetry";
+
+    fn random_source(rng: &mut Rng) -> String {
+        let len = below(rng, 48);
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| {
+                if below(rng, 4) == 0 {
+                    rng.next_u64() as u8
+                } else {
+                    INTERESTING[below(rng, INTERESTING.len())]
+                }
+            })
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    fn scan_and_compile(source: &str) {
+        scan(source);
+        let mut strings = InternMap::new();
+        let compiler = Compiler::with_strings(Source::new(source), &mut strings);
+        let _ = compiler.compile();
+    }
+
+    #[test]
+    fn input_ending_mid_token() {
+        let sources = [
+            "!", "=", "<", ">", "/*", "/* /*", "1.", "1e", "0x", "\"", "\"${", "\"\\", "\"\\u{",
+        ];
+        for source in sources.iter() {
+            scan_and_compile(source);
+        }
+    }
+
+    #[test]
+    fn random_input_never_panics() {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..5000 {
+            scan_and_compile(&random_source(&mut rng));
+        }
     }
 }
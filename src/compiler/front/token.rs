@@ -30,8 +30,11 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
+    DotDot,
     Minus,
     Plus,
     Semicolon,
@@ -39,6 +42,11 @@ pub enum TokenKind {
     Star,
     QuestionMark,
     Colon,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two characters
     Bang,
@@ -49,10 +57,23 @@ pub enum TokenKind {
     GreaterEqual,
     Less,
     LessEqual,
+    PlusEqual,
+    PlusPlus,
+    MinusEqual,
+    MinusMinus,
+    StarEqual,
+    SlashEqual,
+    StarStar,
+    TildeSlash,
+    LessLess,
+    GreaterGreater,
 
     // Literals
     Identifier,
     String,
+    /// The part of a string literal before an interpolated expression
+    Interpolation,
+    Integer,
     Number,
 
     Keyword(Keyword),
@@ -104,6 +125,15 @@ pub enum Keyword {
     True,
     Let,
     While,
+    Switch,
+    Case,
+    Default,
+    In,
+    Import,
+    Try,
+    Catch,
+    Finally,
+    Throw,
 }
 
 #[derive(Ord, Debug, PartialOrd, Eq, PartialEq, Copy, Clone)]
@@ -115,9 +145,15 @@ pub enum Precedence {
     And,
     Equality,
     Comparison,
+    Range,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     Term,
     Factor,
     Unary,
+    Exponent,
     Call,
     Primary,
 }
@@ -132,11 +168,17 @@ impl From<u32> for Precedence {
             3 => And,
             4 => Equality,
             5 => Comparison,
-            6 => Term,
-            7 => Factor,
-            8 => Unary,
-            9 => Call,
-            10 => Primary,
+            6 => Range,
+            7 => BitOr,
+            8 => BitXor,
+            9 => BitAnd,
+            10 => Shift,
+            11 => Term,
+            12 => Factor,
+            13 => Unary,
+            14 => Exponent,
+            15 => Call,
+            16 => Primary,
             _ => unreachable!(),
         }
     }
@@ -1,11 +1,9 @@
 mod compiler;
-mod driver;
 mod errors;
 mod front;
 mod source;
 
 pub use compiler::*;
-pub use driver::*;
 pub use errors::*;
 pub use front::*;
 pub use source::*;
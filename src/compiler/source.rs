@@ -1,4 +1,4 @@
-use crate::compiler::Token;
+use crate::compiler::{Token, TokenKind};
 
 #[derive(Copy, Clone)]
 pub struct Source<'src> {
@@ -11,14 +11,15 @@ impl<'src> Source<'src> {
     }
 
     pub fn get_lexeme(&self, token: &Token) -> &str {
-        std::str::from_utf8(&self.source.as_bytes()[token.position.start..token.position.end])
-            .unwrap()
+        &self.source[token.position.start..token.position.end]
     }
 
+    /// Gets the contents of a `String` or `Interpolation` token, without the delimiters.
     pub fn get_string(&self, token: &Token) -> &str {
-        std::str::from_utf8(
-            &self.source.as_bytes()[token.position.start + 1..token.position.end - 1],
-        )
-        .unwrap()
+        let end_delimiter = match token.ty {
+            TokenKind::Interpolation => 2,
+            _ => 1,
+        };
+        &self.source[token.position.start + 1..token.position.end - end_delimiter]
     }
 }
@@ -51,16 +51,28 @@ impl Disassembler {
                 Ret => self.simple(opcode, offset),
                 Push => self.offset(opcode, chunk, offset),
                 Neg => self.simple(opcode, offset),
-                Add | Sub | Mul | Div => self.simple(opcode, offset),
+                Add | Sub | Mul | Div | Mod | Pow | FloorDiv => self.simple(opcode, offset),
+                BitAnd | BitOr | BitXor | BitNot | Shl | Shr => self.simple(opcode, offset),
                 True | False | Nil | Not => self.simple(opcode, offset),
-                Eq | Lt | Gt => self.simple(opcode, offset),
+                Eq | Ne | Lt | Le | Gt | Ge => self.simple(opcode, offset),
+                Stringify => self.simple(opcode, offset),
                 Print => self.simple(opcode, offset),
                 Pop => self.simple(opcode, offset),
                 DefineGlobal => self.offset(opcode, chunk, offset),
                 GetGlobal | SetGlobal => self.offset(opcode, chunk, offset),
                 GetLocal | SetLocal => self.byte(opcode, chunk, offset),
                 JZ | JMP => self.jump(opcode, 1, chunk, offset),
-                LOOP => self.jump(opcode, -1, chunk, offset)
+                LOOP => self.jump(opcode, -1, chunk, offset),
+                JumpTable => self.jump_table(opcode, chunk, offset),
+                BuildList | BuildMap => self.byte(opcode, chunk, offset),
+                BuildRange | GetIndex | SetIndex | GetSlice => self.simple(opcode, offset),
+                IterInit => self.simple(opcode, offset),
+                IterNext => self.iter_next(opcode, chunk, offset),
+                Call => self.byte(opcode, chunk, offset),
+                GetProperty => self.offset(opcode, chunk, offset),
+                Import => self.offset(opcode, chunk, offset),
+                PushHandler => self.jump(opcode, 1, chunk, offset),
+                PopHandler | Throw => self.simple(opcode, offset),
             }
         } else {
             self.pretty_printer.unknown();
@@ -94,6 +106,35 @@ impl Disassembler {
         offset + 3
     }
 
+    fn jump_table(&mut self, opcode: Opcode, chunk: &Chunk, offset: usize) -> usize {
+        let pointer = chunk.code[offset + 1] as usize;
+        let count = chunk.code[offset + 2] as usize;
+        let table_end = offset + 3 + 2 * count;
+
+        self.pretty_printer.opcode(opcode);
+        self.pretty_printer.pointer(pointer);
+        self.pretty_printer.value(&chunk.constants.values[pointer]);
+        for entry in 0..count {
+            let mut jump: usize = (chunk.code[offset + 3 + 2 * entry] as usize) << 8;
+            jump |= chunk.code[offset + 4 + 2 * entry] as usize;
+            if jump != 0 {
+                self.pretty_printer.chunk_offset(table_end - jump);
+            }
+        }
+        table_end
+    }
+
+    fn iter_next(&mut self, opcode: Opcode, chunk: &Chunk, offset: usize) -> usize {
+        let slot = chunk.code[offset + 1];
+        let mut jump: usize = (chunk.code[offset + 2] as usize) << 8;
+        jump |= chunk.code[offset + 3] as usize;
+
+        self.pretty_printer.opcode(opcode);
+        self.pretty_printer.local(slot);
+        self.pretty_printer.chunk_offset(offset + 4 + jump);
+        offset + 4
+    }
+
     fn byte(&mut self, opcode: Opcode, chunk: &Chunk, offset: usize) -> usize {
         let slot = chunk.code[offset + 1];
 
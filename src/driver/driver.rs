@@ -1,57 +1,56 @@
-use crate::compiler::{compile, CompileError, Source};
+use crate::interpreter::{InterpretError, Interpreter};
 use crate::utils::PrettyPrinter;
-use crate::vm::{RuntimeError, VM};
+use crate::vm::{RuntimeError, RuntimeErrorKind};
 use std::fs::File;
 use std::io;
-use std::io::Read;
-
-pub enum InterpretError {
-    CompileError(CompileError),
-    RuntimeError(RuntimeError),
-}
-
-pub type InterpretResult = Result<(), InterpretError>;
+use std::io::{Read, Write};
+use std::process;
 
 pub fn repl() {
+    let mut interpreter = Interpreter::new();
     let mut input = String::new();
     let mut pretty_printer = PrettyPrinter::new(String::new());
 
     loop {
         input.clear();
         pretty_printer.prompt().print();
-        io::stdin().read_line(&mut input).unwrap();
+        if io::stdin().read_line(&mut input).unwrap() == 0 {
+            // End of input
+            println!();
+            return;
+        }
         input.pop();
         if input.len() > 0 {
-            interpret(&input)
-                .err()
-                .map(|err| pretty_printer.interpret_error(err).newline().print());
+            if let Err(err) = interpreter.eval(&input) {
+                report_error(err, &mut pretty_printer);
+            }
         } else {
             println!();
         }
     }
 }
 
-pub fn run_file(path: &str) {
+pub fn run_file(path: &str, script_args: &[String]) {
     let mut s = String::new();
     File::open(&path).unwrap().read_to_string(&mut s).unwrap();
 
-    let result = interpret(&s);
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script_args(script_args.to_vec());
+    interpreter.set_script_path(path);
+    if let Err(err) = interpreter.eval(&s) {
+        report_error(err, &mut PrettyPrinter::new(String::new()));
+    }
 }
 
-pub fn interpret(src: &str) -> InterpretResult {
-    use InterpretError::*;
-
-    let source = Source::new(src);
-
-    let (chunk, strings, local_map) = match compile(source) {
-        Ok(res) => res,
-        Err(err) => return Err(CompileError(err)),
-    };
-
-    let mut vm = VM::new(&chunk, strings, local_map);
-    match vm.interpret() {
-        Ok(_) => (),
-        Err(err) => return Err(RuntimeError(err)),
-    };
-    Ok(())
+/// Prints an error, or exits the process if the script asked to with `exit`.
+fn report_error(err: InterpretError, pretty_printer: &mut PrettyPrinter) {
+    if let InterpretError::RuntimeError(RuntimeError {
+        kind: RuntimeErrorKind::Exit(code),
+        ..
+    }) = err
+    {
+        io::stdout().flush().ok();
+        process::exit(code);
+    }
+    pretty_printer.interpret_error(err).newline().print();
 }
@@ -0,0 +1,240 @@
+use crate::bytecode::{Arity, Chunk, InternMap, LocalMap, NativeFn, Value};
+use crate::compiler::{CompileError, Compiler, Source};
+use crate::stdlib::Capabilities;
+use crate::vm::{OutputFn, RuntimeError, VM};
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum InterpretError {
+    CompileError(CompileError),
+    RuntimeError(RuntimeError),
+}
+
+impl fmt::Display for InterpretError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterpretError::CompileError(err) => write!(f, "{}", err),
+            InterpretError::RuntimeError(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for InterpretError {}
+
+impl From<CompileError> for InterpretError {
+    fn from(err: CompileError) -> InterpretError {
+        InterpretError::CompileError(err)
+    }
+}
+
+impl From<RuntimeError> for InterpretError {
+    fn from(err: RuntimeError) -> InterpretError {
+        InterpretError::RuntimeError(err)
+    }
+}
+
+pub type InterpretResult = Result<(), InterpretError>;
+
+/// The entry point for embedding rlox. Globals, natives and interned strings persist from one
+/// `eval` to the next, so a host can run several sources against the same state.
+pub struct Interpreter {
+    vm: VM,
+    /// Where script output goes while it's being captured
+    captured: Option<Rc<RefCell<Vec<u8>>>>,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter::with_capabilities(Capabilities::all())
+    }
+
+    /// Creates an interpreter whose natives can only use `capabilities`.
+    pub fn with_capabilities(capabilities: Capabilities) -> Interpreter {
+        let vm = VM::with_capabilities(
+            Chunk::new(),
+            InternMap::new(),
+            LocalMap::new(),
+            capabilities,
+        );
+        Interpreter { vm, captured: None }
+    }
+
+    /// Compiles and runs `source`.
+    pub fn eval(&mut self, source: &str) -> InterpretResult {
+        let compiler = Compiler::with_strings(Source::new(source), self.vm.strings_mut());
+        let (chunk, _locals) = compiler.compile()?;
+
+        self.vm.load(chunk);
+        self.run()
+    }
+
+    /// Runs the loaded code until it finishes or stops, naming the script in runtime errors.
+    fn run(&mut self) -> InterpretResult {
+        self.vm
+            .interpret()
+            .map_err(|err| match self.vm.script_path() {
+                Some(path) => err.in_file(path),
+                None => err,
+            })?;
+        Ok(())
+    }
+
+    /// Says which file the sources passed to `eval` come from, so imports are resolved next to
+    /// it and runtime errors name it.
+    pub fn set_script_path<P: Into<PathBuf>>(&mut self, path: P) {
+        self.vm.set_script_path(Some(path.into()));
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.vm.get_global(name).cloned()
+    }
+
+    /// Defines or replaces the global `name`.
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.vm.define_global(name, value);
+    }
+
+    /// Makes a string value from the host, sharing storage with equal strings in scripts.
+    pub fn new_string(&mut self, string: &str) -> Value {
+        self.vm.new_string(string)
+    }
+
+    pub fn register_native(&mut self, name: &str, arity: Arity, function: NativeFn) {
+        self.vm.define_native(name, arity, function);
+    }
+
+    /// Sets what the `args` native gives back.
+    pub fn set_script_args(&mut self, script_args: Vec<String>) {
+        self.vm.set_script_args(script_args);
+    }
+
+    /// Sends everything scripts `print` to `output` instead of stdout.
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.vm.set_output(output);
+        self.captured = None;
+    }
+
+    /// Hands everything scripts `print` to `callback`. Each write arrives as text, so a line may
+    /// come in several pieces.
+    pub fn set_output_fn<F: FnMut(&str) + 'static>(&mut self, callback: F) {
+        self.set_output(Box::new(OutputFn(callback)));
+    }
+
+    /// Sends what scripts `eprint` to `error_output` instead of stderr.
+    pub fn set_error_output(&mut self, error_output: Box<dyn Write>) {
+        self.vm.set_error_output(error_output);
+    }
+
+    /// Sets where the `trace_execution` feature writes its traces, which are kept apart from the
+    /// script's output. Does nothing without the feature.
+    pub fn set_trace_output(&mut self, trace: Box<dyn Write>) {
+        self.vm.set_trace_output(trace);
+    }
+
+    /// Collects everything scripts `print` from now on, instead of writing it to stdout.
+    pub fn capture_output(&mut self) {
+        let buffer = Rc::new(RefCell::new(Vec::new()));
+        self.vm.set_output(Box::new(SharedBuffer(buffer.clone())));
+        self.captured = Some(buffer);
+    }
+
+    /// Returns the output captured since the last call, leaving the buffer empty.
+    pub fn take_output(&mut self) -> String {
+        match &self.captured {
+            Some(buffer) => {
+                let bytes = std::mem::take(&mut *buffer.borrow_mut());
+                String::from_utf8_lossy(&bytes).into_owned()
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Limits how many more instructions can run before the script stops with a
+    /// `BudgetExhausted` error, or removes the limit with `None`.
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.vm.set_instruction_budget(budget);
+    }
+
+    /// Makes the script stop with a `Timeout` error once `deadline` has passed.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.vm.set_deadline(deadline);
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.vm.set_timeout(timeout);
+    }
+
+    /// Limits how many values the stack can hold before the script fails with a stack
+    /// overflow.
+    pub fn set_max_stack(&mut self, max_stack: Option<usize>) {
+        self.vm.set_max_stack(max_stack);
+    }
+
+    /// Limits the total bytes of strings, lists and maps a single `eval` can create. The count
+    /// starts again at zero for each `eval`, and isn't reduced when objects are dropped.
+    pub fn set_max_heap_bytes(&mut self, max_heap_bytes: Option<usize>) {
+        self.vm.set_max_heap_bytes(max_heap_bytes);
+    }
+
+    /// Bytes allocated by the current or last `eval`, as counted against the heap limit.
+    pub fn allocated_bytes(&self) -> usize {
+        self.vm.allocated_bytes()
+    }
+
+    pub fn instructions_executed(&self) -> u64 {
+        self.vm.instructions_executed()
+    }
+
+    /// Continues a script that was stopped by its budget or deadline, once the limit has been
+    /// raised.
+    pub fn resume(&mut self) -> InterpretResult {
+        self.run()
+    }
+
+    /// Gives up on a script that was stopped by its budget or deadline, so that `resume` just
+    /// returns.
+    pub fn abort(&mut self) {
+        self.vm.abort();
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_errors_keep_interned_strings() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(r#"let greeting = "hello";"#).unwrap();
+        let before = interpreter.vm.strings_mut()["hello"].clone();
+        assert!(interpreter.eval(r#"let broken = "hello" +;"#).is_err());
+        interpreter.eval(r#"let again = "hello";"#).unwrap();
+        let after = interpreter.vm.strings_mut()["hello"].clone();
+        assert!(Rc::ptr_eq(&before, &after));
+    }
+}
@@ -0,0 +1,3 @@
+mod interpreter;
+
+pub use interpreter::*;
@@ -0,0 +1,23 @@
+//! A bytecode interpreter for Lox that can be embedded in Rust programs through
+//! [`Interpreter`].
+
+extern crate ansi_term;
+
+mod bytecode;
+mod compiler;
+mod debug;
+#[doc(hidden)]
+pub mod driver;
+mod interpreter;
+mod stdlib;
+mod utils;
+mod vm;
+
+pub use bytecode::{
+    Arity, ConversionError, ErrorObject, FinalizerFn, Foreign, ForeignClass, ForeignGetterFn,
+    ForeignMethodFn, NativeError, NativeFn, NativeResult, Obj, Value,
+};
+pub use compiler::{CompileError, SyntaxError};
+pub use interpreter::{InterpretError, InterpretResult, Interpreter};
+pub use stdlib::{Capabilities, Capability};
+pub use vm::{RuntimeError, RuntimeErrorKind, VM};
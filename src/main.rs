@@ -1,13 +1,4 @@
-extern crate ansi_term;
-
-mod bytecode;
-mod compiler;
-mod debug;
-mod driver;
-mod utils;
-mod vm;
-
-use crate::driver::{repl, run_file};
+use rlox::driver::{repl, run_file};
 use std::env;
 
 fn main() {
@@ -15,9 +6,8 @@ fn main() {
 
     if args.len() == 1 {
         repl();
-    } else if args.len() == 2 {
-        run_file(&args[1]);
     } else {
-        println!("Usage: rlox [path]");
+        // Anything after the script path is passed through to the script
+        run_file(&args[1], &args[2..]);
     }
 }
@@ -0,0 +1,40 @@
+use crate::bytecode::{List, Map, Obj, Value};
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+pub fn argument_error(native: &str, expected: &str, got: &Value) -> String {
+    format!("{}() expects {}, got {}", native, expected, got.type_name())
+}
+
+pub fn expect_list<'a>(native: &str, value: &'a Value) -> Result<&'a List, String> {
+    match value {
+        Value::Obj(Obj::List(list)) => Ok(list),
+        other => Err(argument_error(native, "a list", other)),
+    }
+}
+
+pub fn expect_map<'a>(native: &str, value: &'a Value) -> Result<&'a Map, String> {
+    match value {
+        Value::Obj(Obj::Map(map)) => Ok(map),
+        other => Err(argument_error(native, "a map", other)),
+    }
+}
+
+pub fn expect_string<'a>(native: &str, value: &'a Value) -> Result<&'a Rc<String>, String> {
+    match value {
+        Value::Obj(Obj::String(string)) => Ok(string),
+        other => Err(argument_error(native, "a string", other)),
+    }
+}
+
+pub fn expect_int(native: &str, value: &Value) -> Result<i64, String> {
+    match value {
+        Value::Int(num) => Ok(*num),
+        other => Err(argument_error(native, "an int", other)),
+    }
+}
+
+/// Accepts ints too, converting them to floats.
+pub fn expect_number(native: &str, value: &Value) -> Result<f64, String> {
+    f64::try_from(value.clone()).map_err(|_| argument_error(native, "a number", value))
+}
@@ -0,0 +1,200 @@
+use crate::bytecode::{Arity, NativeFn, NativeResult, Value};
+use crate::vm::VM;
+
+/// A group of natives that reach outside the VM, which embedders can withhold from untrusted
+/// scripts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Reading and writing files
+    Filesystem,
+    /// Reading environment variables
+    Env,
+    /// Command line arguments, the standard streams and `exit`
+    Process,
+    /// The current time
+    Clock,
+}
+
+/// The capabilities a VM's natives are allowed to use.
+#[derive(Copy, Clone, Debug)]
+pub struct Capabilities {
+    filesystem: bool,
+    env: bool,
+    process: bool,
+    clock: bool,
+}
+
+impl Capabilities {
+    pub fn all() -> Capabilities {
+        Capabilities {
+            filesystem: true,
+            env: true,
+            process: true,
+            clock: true,
+        }
+    }
+
+    pub fn none() -> Capabilities {
+        Capabilities {
+            filesystem: false,
+            env: false,
+            process: false,
+            clock: false,
+        }
+    }
+
+    pub fn allow(mut self, capability: Capability) -> Capabilities {
+        *self.flag(capability) = true;
+        self
+    }
+
+    pub fn deny(mut self, capability: Capability) -> Capabilities {
+        *self.flag(capability) = false;
+        self
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Filesystem => self.filesystem,
+            Capability::Env => self.env,
+            Capability::Process => self.process,
+            Capability::Clock => self.clock,
+        }
+    }
+
+    fn flag(&mut self, capability: Capability) -> &mut bool {
+        match capability {
+            Capability::Filesystem => &mut self.filesystem,
+            Capability::Env => &mut self.env,
+            Capability::Process => &mut self.process,
+            Capability::Clock => &mut self.clock,
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Capabilities {
+        Capabilities::all()
+    }
+}
+
+/// Defines a native that needs `capability`. Without it the name is still defined, but calling
+/// it is a runtime error, so scripts fail with a clear message instead of an undefined variable.
+pub fn define_gated_native(
+    vm: &mut VM,
+    capability: Capability,
+    name: &str,
+    arity: Arity,
+    function: NativeFn,
+) {
+    let function = if vm.capabilities().allows(capability) {
+        function
+    } else {
+        denied(capability)
+    };
+    vm.define_native(name, arity, function);
+}
+
+fn denied(capability: Capability) -> NativeFn {
+    match capability {
+        Capability::Filesystem => no_filesystem,
+        Capability::Env => no_env,
+        Capability::Process => no_process,
+        Capability::Clock => no_clock,
+    }
+}
+
+fn no_filesystem(_vm: &mut VM, _args: &[Value]) -> NativeResult {
+    Err("This script isn't allowed to access the filesystem"
+        .to_owned()
+        .into())
+}
+
+fn no_env(_vm: &mut VM, _args: &[Value]) -> NativeResult {
+    Err("This script isn't allowed to read environment variables"
+        .to_owned()
+        .into())
+}
+
+fn no_process(_vm: &mut VM, _args: &[Value]) -> NativeResult {
+    Err("This script isn't allowed to access the process"
+        .to_owned()
+        .into())
+}
+
+fn no_clock(_vm: &mut VM, _args: &[Value]) -> NativeResult {
+    Err("This script isn't allowed to read the clock"
+        .to_owned()
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::InternMap;
+    use crate::compiler::{Compiler, Source};
+
+    /// Runs `source` with `capabilities`, giving back the runtime error message if it failed.
+    fn run(capabilities: Capabilities, source: &str) -> Result<(), String> {
+        let mut strings = InternMap::new();
+        let compiler = Compiler::with_strings(Source::new(source), &mut strings);
+        let (chunk, locals) = match compiler.compile() {
+            Ok(compiled) => compiled,
+            Err(_) => panic!("{} should have compiled", source),
+        };
+        let mut vm = VM::with_capabilities(chunk, strings, locals, capabilities);
+        vm.interpret().map_err(|err| err.message)
+    }
+
+    fn denied(capabilities: Capabilities, source: &str) -> String {
+        run(capabilities, source).expect_err(source)
+    }
+
+    #[test]
+    fn denied_natives_are_runtime_errors() {
+        let none = Capabilities::none();
+        let filesystem = "This script isn't allowed to access the filesystem";
+        assert_eq!(denied(none, r#"read_file("x");"#), filesystem);
+        assert_eq!(denied(none, r#"write_file("x", "y");"#), filesystem);
+        assert_eq!(denied(none, r#"append_file("x", "y");"#), filesystem);
+        let env = "This script isn't allowed to read environment variables";
+        assert_eq!(denied(none, r#"env("HOME");"#), env);
+        let process = "This script isn't allowed to access the process";
+        assert_eq!(denied(none, "args();"), process);
+        assert_eq!(denied(none, "exit(1);"), process);
+        assert_eq!(denied(none, r#"eprint("x");"#), process);
+        assert_eq!(denied(none, "read_line();"), process);
+        assert_eq!(
+            denied(none, "clock();"),
+            "This script isn't allowed to read the clock"
+        );
+    }
+
+    #[test]
+    fn denying_one_capability_leaves_the_others() {
+        let capabilities = Capabilities::all().deny(Capability::Filesystem);
+        assert_eq!(run(capabilities, "clock(); args();"), Ok(()));
+        assert_eq!(
+            denied(capabilities, r#"read_file("x");"#),
+            "This script isn't allowed to access the filesystem"
+        );
+    }
+
+    #[test]
+    fn allowing_one_capability() {
+        let capabilities = Capabilities::none().allow(Capability::Clock);
+        assert!(capabilities.allows(Capability::Clock));
+        assert!(!capabilities.allows(Capability::Env));
+        assert_eq!(run(capabilities, "clock();"), Ok(()));
+        assert_eq!(
+            denied(capabilities, r#"env("HOME");"#),
+            "This script isn't allowed to read environment variables"
+        );
+    }
+
+    #[test]
+    fn pure_natives_need_no_capabilities() {
+        let source = r#"sqrt(4); upper("a"); len([1]); random();"#;
+        assert_eq!(run(Capabilities::none(), source), Ok(()));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::bytecode::{Arity, HashKey, NativeResult, Obj, Value};
+use crate::stdlib::{argument_error, expect_list, expect_map};
+use crate::vm::VM;
+use std::mem::size_of;
+
+pub fn register_collections(vm: &mut VM) {
+    vm.define_native("len", Arity::Exactly(1), len);
+    vm.define_native("push", Arity::Exactly(2), push);
+    vm.define_native("pop", Arity::Exactly(1), pop);
+    vm.define_native("has", Arity::Exactly(2), has);
+    vm.define_native("keys", Arity::Exactly(1), keys);
+    vm.define_native("remove", Arity::Exactly(2), remove);
+}
+
+fn len(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    match &args[0] {
+        Value::Obj(Obj::List(list)) => Ok(Value::Int(list.borrow().len() as i64)),
+        Value::Obj(Obj::Map(map)) => Ok(Value::Int(map.borrow().len() as i64)),
+        Value::Obj(Obj::Range(range)) => Ok(Value::Int(range.len() as i64)),
+        Value::Obj(Obj::String(string)) => Ok(Value::Int(string.chars().count() as i64)),
+        other => Err(argument_error("len", "a list, map, range or string", other).into()),
+    }
+}
+
+fn push(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let list = expect_list("push", &args[0])?;
+    list.borrow_mut().push(args[1].clone());
+    vm.track_allocation(size_of::<Value>());
+    Ok(Value::Nil)
+}
+
+fn pop(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let list = expect_list("pop", &args[0])?;
+    let popped = list.borrow_mut().pop();
+    popped.ok_or_else(|| "pop() called on an empty list".to_owned().into())
+}
+
+fn has(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let map = expect_map("has", &args[0])?;
+    let key = HashKey::new(args[1].clone())?;
+    let has = map.borrow().contains_key(&key);
+    Ok(Value::Bool(has))
+}
+
+/// Lists the keys of a map in insertion order.
+fn keys(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let map = expect_map("keys", &args[0])?;
+    let keys = map.borrow().keys().cloned().collect();
+    Ok(vm.new_list(keys))
+}
+
+fn remove(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let map = expect_map("remove", &args[0])?;
+    let key = HashKey::new(args[1].clone())?;
+    let removed = map.borrow_mut().remove(&key);
+    removed.ok_or_else(|| format!("Key {} not found in map", args[1]).into())
+}
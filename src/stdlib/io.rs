@@ -0,0 +1,109 @@
+use crate::bytecode::{Arity, NativeError, NativeResult, Value};
+use crate::stdlib::{define_gated_native, expect_int, expect_string, Capability};
+use crate::vm::VM;
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::{BufRead, Write};
+
+/// Natives for files, the standard streams and the process. Failures become runtime errors
+/// that say what went wrong rather than panics.
+pub fn register_io(vm: &mut VM) {
+    use Capability::*;
+    define_gated_native(vm, Filesystem, "read_file", Arity::Exactly(1), read_file);
+    define_gated_native(vm, Filesystem, "write_file", Arity::Exactly(2), write_file);
+    define_gated_native(vm, Filesystem, "append_file", Arity::Exactly(2), append_file);
+    define_gated_native(vm, Process, "read_line", Arity::Exactly(0), read_line);
+    define_gated_native(vm, Process, "eprint", Arity::Exactly(1), eprint);
+    define_gated_native(vm, Env, "env", Arity::Exactly(1), env_var);
+    define_gated_native(vm, Process, "args", Arity::Exactly(0), args);
+    define_gated_native(vm, Process, "exit", Arity::Between(0, 1), exit);
+}
+
+fn read_file(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let path = expect_string("read_file", &args[0])?;
+    match fs::read_to_string(path.as_str()) {
+        Ok(contents) => Ok(vm.new_string(&contents)),
+        Err(err) => Err(io_error("read_file", path, err)),
+    }
+}
+
+/// Replaces the contents of a file, creating it if it doesn't exist.
+fn write_file(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let path = expect_string("write_file", &args[0])?;
+    let contents = expect_string("write_file", &args[1])?;
+    match fs::write(path.as_str(), contents.as_bytes()) {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error("write_file", path, err)),
+    }
+}
+
+fn append_file(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let path = expect_string("append_file", &args[0])?;
+    let contents = expect_string("append_file", &args[1])?;
+    let result = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path.as_str())
+        .and_then(|mut file| file.write_all(contents.as_bytes()));
+    match result {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error("append_file", path, err)),
+    }
+}
+
+/// Reads a line from stdin without its line ending, or gives nil at the end of input.
+fn read_line(vm: &mut VM, _args: &[Value]) -> NativeResult {
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) => Ok(Value::Nil),
+        Ok(_) => {
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            Ok(vm.new_string(line))
+        }
+        Err(err) => Err(format!("read_line() couldn't read stdin: {}", err).into()),
+    }
+}
+
+/// Like `print`, but to the VM's error output, which is stderr unless the host redirected it.
+fn eprint(vm: &mut VM, args: &[Value]) -> NativeResult {
+    match writeln!(vm.error_output(), "{}", args[0]) {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(format!("eprint() couldn't write: {}", err).into()),
+    }
+}
+
+/// Gives the value of an environment variable, or nil if it isn't set.
+fn env_var(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let name = expect_string("env", &args[0])?;
+    match env::var(name.as_str()) {
+        Ok(value) => Ok(vm.new_string(&value)),
+        Err(_) => Ok(Value::Nil),
+    }
+}
+
+/// The command line arguments given after the script path.
+fn args(vm: &mut VM, _args: &[Value]) -> NativeResult {
+    let script_args = vm.script_args().to_vec();
+    let script_args = script_args.iter().map(|arg| vm.new_string(arg)).collect();
+    Ok(vm.new_list(script_args))
+}
+
+/// `exit()` or `exit(code)`. Stops the script straight away, with 0 as the default code.
+fn exit(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let code = match args.first() {
+        Some(code) => expect_int("exit", code)?,
+        None => 0,
+    };
+    match i32::try_from(code) {
+        Ok(code) => Err(NativeError::Exit(code)),
+        Err(_) => Err(format!("exit() code {} is out of range", code).into()),
+    }
+}
+
+fn io_error(native: &str, path: &str, err: io::Error) -> NativeError {
+    format!("{}() failed for '{}': {}", native, path, err).into()
+}
@@ -0,0 +1,213 @@
+use crate::bytecode::{Arity, NativeResult, Obj, Value};
+use crate::stdlib::{argument_error, expect_int, expect_number};
+use crate::vm::{int_pow, numeric_op, VM};
+use std::f64::consts::PI;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn register_math(vm: &mut VM) {
+    vm.define_builtin("pi", Value::Number(PI));
+    vm.define_builtin("inf", Value::Number(f64::INFINITY));
+    vm.define_builtin("nan", Value::Number(f64::NAN));
+
+    vm.define_native("sqrt", Arity::Exactly(1), sqrt);
+    vm.define_native("pow", Arity::Exactly(2), pow);
+    vm.define_native("exp", Arity::Exactly(1), exp);
+    vm.define_native("log", Arity::Exactly(1), log);
+    vm.define_native("floor", Arity::Exactly(1), floor);
+    vm.define_native("ceil", Arity::Exactly(1), ceil);
+    vm.define_native("round", Arity::Exactly(1), round);
+    vm.define_native("abs", Arity::Exactly(1), abs);
+    vm.define_native("min", Arity::AtLeast(1), min);
+    vm.define_native("max", Arity::AtLeast(1), max);
+    vm.define_native("sin", Arity::Exactly(1), sin);
+    vm.define_native("cos", Arity::Exactly(1), cos);
+    vm.define_native("tan", Arity::Exactly(1), tan);
+    vm.define_native("asin", Arity::Exactly(1), asin);
+    vm.define_native("acos", Arity::Exactly(1), acos);
+    vm.define_native("atan", Arity::Exactly(1), atan);
+    vm.define_native("atan2", Arity::Exactly(2), atan2);
+    vm.define_native("seed", Arity::Exactly(1), seed);
+    vm.define_native("random", Arity::Exactly(0), random);
+    vm.define_native("random_int", Arity::Exactly(2), random_int);
+}
+
+/// A SplitMix64 generator. It's not cryptographically secure, but it's fast, tiny and any seed
+/// gives a good sequence.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn from_time() -> Rng {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Rng::new(now.as_nanos() as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A float in `[0, 1)`, using the top 53 bits so every value is equally likely.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn float_fn(native: &str, args: &[Value], f: fn(f64) -> f64) -> NativeResult {
+    Ok(Value::Number(f(expect_number(native, &args[0])?)))
+}
+
+/// Rounds a float with `f`, giving back an int when the result fits in one. Ints are already
+/// whole, so they're returned unchanged.
+fn rounding_fn(native: &str, args: &[Value], f: fn(f64) -> f64) -> NativeResult {
+    match &args[0] {
+        Value::Int(num) => Ok(Value::Int(*num)),
+        value => {
+            let rounded = f(expect_number(native, value)?);
+            if rounded.is_finite() && rounded >= i64::MIN as f64 && rounded < i64::MAX as f64 {
+                Ok(Value::Int(rounded as i64))
+            } else {
+                Ok(Value::Number(rounded))
+            }
+        }
+    }
+}
+
+fn sqrt(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("sqrt", args, f64::sqrt)
+}
+
+/// The same as `a ** b`.
+fn pow(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let (left, right) = (args[0].clone(), args[1].clone());
+    if !left.is_number() || !right.is_number() {
+        let culprit = if left.is_number() { &args[1] } else { &args[0] };
+        return Err(argument_error("pow", "two numbers", culprit).into());
+    }
+    numeric_op(left, right, int_pow, |left, right| Value::Number(left.powf(right)))
+        .map_err(|message| message.to_owned().into())
+}
+
+fn exp(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("exp", args, f64::exp)
+}
+
+fn log(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("log", args, f64::ln)
+}
+
+fn floor(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    rounding_fn("floor", args, f64::floor)
+}
+
+fn ceil(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    rounding_fn("ceil", args, f64::ceil)
+}
+
+fn round(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    rounding_fn("round", args, f64::round)
+}
+
+fn abs(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    match &args[0] {
+        Value::Int(num) => num
+            .checked_abs()
+            .map(Value::Int)
+            .ok_or_else(|| "Integer overflow".to_owned().into()),
+        _ => float_fn("abs", args, f64::abs),
+    }
+}
+
+/// `min(a, b, ...)` or `min(list)`.
+fn min(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    extreme("min", args, |candidate, best| candidate < best)
+}
+
+/// `max(a, b, ...)` or `max(list)`.
+fn max(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    extreme("max", args, |candidate, best| candidate > best)
+}
+
+/// Finds the number that `better` prefers over all the others, keeping whether it was an int.
+fn extreme(native: &str, args: &[Value], better: fn(f64, f64) -> bool) -> NativeResult {
+    let values = match args {
+        [Value::Obj(Obj::List(list))] => list.borrow().clone(),
+        args => args.to_vec(),
+    };
+
+    let mut best: Option<(f64, Value)> = None;
+    for value in values {
+        let num = expect_number(native, &value)?;
+        let is_better = match &best {
+            Some((best, _)) => better(num, *best),
+            None => true,
+        };
+        if is_better {
+            best = Some((num, value));
+        }
+    }
+    best.map(|(_, value)| value)
+        .ok_or_else(|| format!("{}() called on an empty list", native).into())
+}
+
+fn sin(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("sin", args, f64::sin)
+}
+
+fn cos(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("cos", args, f64::cos)
+}
+
+fn tan(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("tan", args, f64::tan)
+}
+
+fn asin(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("asin", args, f64::asin)
+}
+
+fn acos(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("acos", args, f64::acos)
+}
+
+fn atan(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    float_fn("atan", args, f64::atan)
+}
+
+fn atan2(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let y = expect_number("atan2", &args[0])?;
+    let x = expect_number("atan2", &args[1])?;
+    Ok(Value::Number(y.atan2(x)))
+}
+
+/// Reseeds the generator, so the numbers that follow are the same on every run.
+fn seed(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let seed = expect_int("seed", &args[0])?;
+    *vm.rng() = Rng::new(seed as u64);
+    Ok(Value::Nil)
+}
+
+fn random(vm: &mut VM, _args: &[Value]) -> NativeResult {
+    Ok(Value::Number(vm.rng().next_f64()))
+}
+
+/// An int from `low` up to but not including `high`, like the range `low..high`.
+fn random_int(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let low = expect_int("random_int", &args[0])?;
+    let high = expect_int("random_int", &args[1])?;
+    if high <= low {
+        let message = format!("random_int() needs low < high, got {} and {}", low, high);
+        return Err(message.into());
+    }
+    let span = (high as i128 - low as i128) as u128;
+    let offset = vm.rng().next_u64() as u128 % span;
+    Ok(Value::Int((low as i128 + offset as i128) as i64))
+}
@@ -0,0 +1,26 @@
+mod args;
+mod capabilities;
+mod collections;
+mod io;
+mod math;
+mod strings;
+mod time;
+
+pub use args::*;
+pub use capabilities::*;
+pub use collections::*;
+pub use io::*;
+pub use math::*;
+pub use strings::*;
+pub use time::*;
+
+use crate::vm::VM;
+
+/// Defines every standard library native as a global of `vm`, gated by its capabilities.
+pub fn register_natives(vm: &mut VM) {
+    register_collections(vm);
+    register_math(vm);
+    register_io(vm);
+    register_time(vm);
+    register_strings(vm);
+}
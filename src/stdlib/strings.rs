@@ -0,0 +1,106 @@
+use crate::bytecode::{Arity, NativeResult, Obj, Value};
+use crate::stdlib::expect_string;
+use crate::vm::{slice_bounds, VM};
+
+/// String natives. Positions are counted in characters rather than bytes, matching `s[i]`.
+pub fn register_strings(vm: &mut VM) {
+    vm.define_native("substring", Arity::Between(2, 3), substring);
+    vm.define_native("find", Arity::Exactly(2), find);
+    vm.define_native("split", Arity::Exactly(2), split);
+    vm.define_native("trim", Arity::Exactly(1), trim);
+    vm.define_native("upper", Arity::Exactly(1), upper);
+    vm.define_native("lower", Arity::Exactly(1), lower);
+    vm.define_native("replace", Arity::Exactly(3), replace);
+    vm.define_native("starts_with", Arity::Exactly(2), starts_with);
+    vm.define_native("ends_with", Arity::Exactly(2), ends_with);
+    vm.define_native("str", Arity::Exactly(1), stringify);
+    vm.define_native("parse_number", Arity::Exactly(1), parse_number);
+}
+
+/// `substring(s, start)` or `substring(s, start, end)`, with the same bounds rules as `s[a:b]`.
+fn substring(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("substring", &args[0])?;
+    let end = args.get(2).unwrap_or(&Value::Nil);
+    let (start, end) = slice_bounds(&args[1], end, string.chars().count())?;
+    let substring: String = string.chars().skip(start).take(end - start).collect();
+    Ok(vm.new_string(&substring))
+}
+
+/// Returns the position of the first occurrence of `needle`, or nil if there isn't one.
+fn find(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("find", &args[0])?;
+    let needle = expect_string("find", &args[1])?;
+    Ok(match string.find(needle.as_str()) {
+        Some(byte) => Value::Int(string[..byte].chars().count() as i64),
+        None => Value::Nil,
+    })
+}
+
+/// Splits on every occurrence of `separator`, or into characters if it's empty.
+fn split(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("split", &args[0])?;
+    let separator = expect_string("split", &args[1])?;
+    let parts: Vec<String> = if separator.is_empty() {
+        string.chars().map(String::from).collect()
+    } else {
+        string.split(separator.as_str()).map(String::from).collect()
+    };
+    let parts = parts.iter().map(|part| vm.new_string(part)).collect();
+    Ok(vm.new_list(parts))
+}
+
+fn trim(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("trim", &args[0])?;
+    Ok(vm.new_string(string.trim()))
+}
+
+fn upper(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("upper", &args[0])?;
+    Ok(vm.new_string(&string.to_uppercase()))
+}
+
+fn lower(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("lower", &args[0])?;
+    Ok(vm.new_string(&string.to_lowercase()))
+}
+
+fn replace(vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("replace", &args[0])?;
+    let from = expect_string("replace", &args[1])?;
+    let to = expect_string("replace", &args[2])?;
+    if from.is_empty() {
+        return Err("replace() can't replace an empty string".to_owned().into());
+    }
+    Ok(vm.new_string(&string.replace(from.as_str(), to)))
+}
+
+fn starts_with(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("starts_with", &args[0])?;
+    let prefix = expect_string("starts_with", &args[1])?;
+    Ok(Value::Bool(string.starts_with(prefix.as_str())))
+}
+
+fn ends_with(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("ends_with", &args[0])?;
+    let suffix = expect_string("ends_with", &args[1])?;
+    Ok(Value::Bool(string.ends_with(suffix.as_str())))
+}
+
+/// Converts any value to a string, the same way `print` shows it.
+fn stringify(vm: &mut VM, args: &[Value]) -> NativeResult {
+    match &args[0] {
+        Value::Obj(Obj::String(_)) => Ok(args[0].clone()),
+        value => Ok(vm.new_string(&value.to_string())),
+    }
+}
+
+/// Parses an int or float literal, ignoring surrounding whitespace. Gives nil when the string
+/// isn't a number, so scripts can check input without failing.
+fn parse_number(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    let string = expect_string("parse_number", &args[0])?.trim();
+    if let Ok(num) = string.parse::<i64>() {
+        Ok(Value::Int(num))
+    } else {
+        Ok(string.parse::<f64>().map_or(Value::Nil, Value::Number))
+    }
+}
@@ -0,0 +1,14 @@
+use crate::bytecode::{Arity, NativeResult, Value};
+use crate::stdlib::{define_gated_native, Capability};
+use crate::vm::VM;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn register_time(vm: &mut VM) {
+    define_gated_native(vm, Capability::Clock, "clock", Arity::Exactly(0), clock);
+}
+
+/// Seconds since the Unix epoch, with a fractional part.
+fn clock(_vm: &mut VM, _args: &[Value]) -> NativeResult {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(Value::Number(now.as_secs_f64()))
+}
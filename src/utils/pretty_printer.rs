@@ -1,6 +1,6 @@
 use crate::bytecode::{Opcode, Value};
-use crate::compiler::{CompileError, Token, TokenKind};
-use crate::driver::InterpretError;
+use crate::compiler::{CompileError, SyntaxError, Token};
+use crate::interpreter::InterpretError;
 use crate::vm::{RuntimeError, Stack};
 use ansi_term::{Color, Style};
 use std::fmt::Write;
@@ -139,19 +139,18 @@ impl PrettyPrinter {
         self
     }
 
-    pub fn parse_error(&mut self, token: &Token, lexeme: &str, message: &str) -> &mut Self {
-        let line = format!("[{}]", token.position.line);
-        let error_pos = match token.ty {
-            TokenKind::EOF => "Error at end".into(),
-            TokenKind::Error(_) => "Error".into(),
-            _ => format!("Error at {}:", lexeme),
+    pub fn syntax_error(&mut self, error: &SyntaxError) -> &mut Self {
+        let line = format!("[{}]", error.line);
+        let error_pos = match &error.location {
+            Some(location) => format!("Error {}:", location),
+            None => "Error:".into(),
         };
         write!(
             self.string,
             "{} {} {}",
             self.line_number.paint(line),
             error_pos,
-            self.error.paint(message)
+            self.error.paint(&error.message)
         )
         .unwrap();
         self
@@ -166,11 +165,20 @@ impl PrettyPrinter {
     }
 
     pub fn compile_error(&mut self, error: CompileError) -> &mut Self {
+        for (i, error) in error.errors.iter().enumerate() {
+            if i > 0 {
+                self.newline();
+            }
+            self.syntax_error(error);
+        }
         self
     }
 
     pub fn runtime_error(&mut self, error: RuntimeError) -> &mut Self {
-        let line = format!("[{}]", error.line);
+        let line = match &error.file {
+            Some(file) => format!("[{}:{}]", file, error.line),
+            None => format!("[{}]", error.line),
+        };
         write!(self.string, "{} {}", line, self.error.paint(error.message)).unwrap();
         self
     }
@@ -1,13 +1,114 @@
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RuntimeErrorKind {
+    Error,
+    /// The script called `exit`, which isn't a failure as such but unwinds the same way.
+    Exit(i32),
+    /// The instruction budget ran out. The VM can be resumed after raising it.
+    BudgetExhausted,
+    /// The deadline passed. The VM can be resumed after extending it.
+    Timeout,
+    /// The value stack grew past its limit.
+    StackOverflow,
+    /// The script allocated more object bytes than it's allowed.
+    OutOfMemory,
+}
+
+impl RuntimeErrorKind {
+    /// Whether the error came from one of the VM's limits, which stop the script between
+    /// instructions so it can be resumed.
+    pub fn is_limit(self) -> bool {
+        use RuntimeErrorKind::*;
+        matches!(
+            self,
+            BudgetExhausted | Timeout | StackOverflow | OutOfMemory
+        )
+    }
+}
+
+#[derive(Debug)]
 pub struct RuntimeError {
     pub line: usize,
+    /// The file the error happened in, when the code came from one
+    pub file: Option<String>,
     pub message: String,
+    pub kind: RuntimeErrorKind,
 }
 
 impl RuntimeError {
     pub fn new(line: usize, message: &str) -> RuntimeError {
         RuntimeError {
             line,
-            message: "".to_string(),
+            file: None,
+            message: message.to_string(),
+            kind: RuntimeErrorKind::Error,
+        }
+    }
+
+    pub fn exit(line: usize, code: i32) -> RuntimeError {
+        RuntimeError {
+            line,
+            file: None,
+            message: format!("Exited with code {}", code),
+            kind: RuntimeErrorKind::Exit(code),
+        }
+    }
+
+    pub fn budget_exhausted(line: usize) -> RuntimeError {
+        RuntimeError {
+            line,
+            file: None,
+            message: "Instruction budget exhausted".to_owned(),
+            kind: RuntimeErrorKind::BudgetExhausted,
         }
     }
+
+    pub fn timeout(line: usize) -> RuntimeError {
+        RuntimeError {
+            line,
+            file: None,
+            message: "Execution timed out".to_owned(),
+            kind: RuntimeErrorKind::Timeout,
+        }
+    }
+
+    pub fn stack_overflow(line: usize) -> RuntimeError {
+        RuntimeError {
+            line,
+            file: None,
+            message: "Stack overflow".to_owned(),
+            kind: RuntimeErrorKind::StackOverflow,
+        }
+    }
+
+    pub fn out_of_memory(line: usize) -> RuntimeError {
+        RuntimeError {
+            line,
+            file: None,
+            message: "Out of memory".to_owned(),
+            kind: RuntimeErrorKind::OutOfMemory,
+        }
+    }
+
+    /// Records that the error happened in `file`, unless a more specific file is already known.
+    pub fn in_file(mut self, file: &Path) -> RuntimeError {
+        if self.file.is_none() {
+            self.file = Some(file.display().to_string());
+        }
+        self
+    }
 }
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "[{}:{}] {}", file, self.line, self.message),
+            None => write!(f, "[{}] {}", self.line, self.message),
+        }
+    }
+}
+
+impl Error for RuntimeError {}
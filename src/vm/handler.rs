@@ -0,0 +1,9 @@
+/// Where to go when an exception is thrown inside a `try` block.
+#[derive(Copy, Clone, Debug)]
+pub struct Handler {
+    /// Start of the code that handles the exception
+    pub ip: usize,
+    /// How tall the stack was when the `try` block started. Anything above it belongs to the
+    /// code that threw, so it's discarded.
+    pub stack_len: usize,
+}
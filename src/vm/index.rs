@@ -0,0 +1,105 @@
+use crate::bytecode::{get_or_insert_string, HashKey, InternMap, Obj, Value, ValueMap};
+
+/// Builds a map out of alternating keys and values, with later duplicate keys winning.
+pub fn build_map(entries: Vec<Value>) -> Result<ValueMap, String> {
+    let mut map = ValueMap::new();
+    let mut entries = entries.into_iter();
+    while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+        map.insert(HashKey::new(key)?, value);
+    }
+    Ok(map)
+}
+
+/// Strings are indexed and sliced by character rather than by byte, giving back strings.
+pub fn get_index(target: &Value, index: &Value, strings: &mut InternMap) -> Result<Value, String> {
+    match target {
+        Value::Obj(Obj::List(list)) => {
+            let list = list.borrow();
+            let index = list_index(index, list.len())?;
+            Ok(list[index].clone())
+        }
+        Value::Obj(Obj::Map(map)) => {
+            let key = HashKey::new(index.clone())?;
+            match map.borrow().get(&key) {
+                Some(value) => Ok(value.clone()),
+                None => Err(format!("Key {} not found in map", index)),
+            }
+        }
+        Value::Obj(Obj::String(string)) => {
+            let index = list_index(index, string.chars().count())?;
+            let mut buffer = [0; 4];
+            let c = string.chars().nth(index).unwrap().encode_utf8(&mut buffer);
+            Ok(Value::Obj(Obj::String(get_or_insert_string(c, strings))))
+        }
+        other => Err(format!("Values of type {} can't be indexed", other.type_name())),
+    }
+}
+
+pub fn set_index(target: &Value, index: &Value, value: Value) -> Result<(), String> {
+    match target {
+        Value::Obj(Obj::List(list)) => {
+            let mut list = list.borrow_mut();
+            let index = list_index(index, list.len())?;
+            list[index] = value;
+            Ok(())
+        }
+        Value::Obj(Obj::Map(map)) => {
+            let key = HashKey::new(index.clone())?;
+            map.borrow_mut().insert(key, value);
+            Ok(())
+        }
+        other => Err(format!(
+            "Values of type {} don't support index assignment",
+            other.type_name()
+        )),
+    }
+}
+
+/// Copies out the elements from `start` up to `end`, either of which can be nil to mean the
+/// start or end of the list.
+pub fn get_slice(
+    target: &Value,
+    start: &Value,
+    end: &Value,
+    strings: &mut InternMap,
+) -> Result<Value, String> {
+    match target {
+        Value::Obj(Obj::List(list)) => {
+            let list = list.borrow();
+            let (start, end) = slice_bounds(start, end, list.len())?;
+            Ok(Value::Obj(Obj::new_list(list[start..end].to_vec())))
+        }
+        Value::Obj(Obj::String(string)) => {
+            let (start, end) = slice_bounds(start, end, string.chars().count())?;
+            let slice: String = string.chars().skip(start).take(end - start).collect();
+            Ok(Value::Obj(Obj::String(get_or_insert_string(&slice, strings))))
+        }
+        other => Err(format!("Values of type {} can't be sliced", other.type_name())),
+    }
+}
+
+fn list_index(index: &Value, len: usize) -> Result<usize, String> {
+    match index {
+        Value::Int(i) if *i >= 0 && (*i as usize) < len => Ok(*i as usize),
+        Value::Int(i) => Err(format!("Index {} out of bounds for length {}", i, len)),
+        other => Err(format!("Index must be an int, got {}", other.type_name())),
+    }
+}
+
+pub fn slice_bounds(start: &Value, end: &Value, len: usize) -> Result<(usize, usize), String> {
+    let start = slice_bound(start, 0, len)?;
+    let end = slice_bound(end, len, len)?;
+    if start > end {
+        return Err(format!("Slice start {} is after its end {}", start, end));
+    }
+    Ok((start, end))
+}
+
+fn slice_bound(bound: &Value, default: usize, len: usize) -> Result<usize, String> {
+    match bound {
+        Value::Nil => Ok(default),
+        Value::Int(i) if *i >= 0 && (*i as usize) <= len => Ok(*i as usize),
+        Value::Int(i) => Err(format!("Slice bound {} out of range for length {}", i, len)),
+        other => Err(format!("Slice bound must be an int, got {}", other.type_name())),
+    }
+}
@@ -0,0 +1,47 @@
+use crate::bytecode::{get_or_insert_string, InternMap, Obj, Range, Value};
+
+pub fn build_range(start: Value, end: Value) -> Result<Value, String> {
+    match (start, end) {
+        (Value::Int(start), Value::Int(end)) => Ok(Value::Obj(Obj::Range(Range { start, end }))),
+        (start, end) => Err(format!(
+            "Range bounds must be ints, got {} and {}",
+            start.type_name(),
+            end.type_name()
+        )),
+    }
+}
+
+/// Turns the value a `for-in` loop is over into something `iter_element` can step through.
+/// Strings become a list of their characters and maps a snapshot of their keys, so changing a
+/// map inside the loop doesn't affect which keys get visited.
+pub fn iter_sequence(value: Value, strings: &mut InternMap) -> Result<Value, String> {
+    match value {
+        Value::Obj(Obj::List(_)) | Value::Obj(Obj::Range(_)) => Ok(value),
+        Value::Obj(Obj::Map(map)) => {
+            let keys = map.borrow().keys().cloned().collect();
+            Ok(Value::Obj(Obj::new_list(keys)))
+        }
+        Value::Obj(Obj::String(string)) => {
+            let mut buffer = [0; 4];
+            let chars = string
+                .chars()
+                .map(|c| get_or_insert_string(c.encode_utf8(&mut buffer), strings))
+                .map(|c| Value::Obj(Obj::String(c)))
+                .collect();
+            Ok(Value::Obj(Obj::new_list(chars)))
+        }
+        other => Err(format!("Values of type {} can't be iterated over", other.type_name())),
+    }
+}
+
+/// Returns the element at `index` of a sequence made by `iter_sequence`, or `None` once it's
+/// been stepped past the end.
+pub fn iter_element(sequence: &Value, index: usize) -> Option<Value> {
+    match sequence {
+        Value::Obj(Obj::List(list)) => list.borrow().get(index).cloned(),
+        Value::Obj(Obj::Range(range)) if index < range.len() => {
+            Some(Value::Int(range.start + index as i64))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,16 @@
+use crate::bytecode::{Obj, Value};
+use std::mem::size_of;
+
+/// Roughly how many heap bytes `value` owns itself. Values inside a collection are counted
+/// when they're created, so they aren't counted again as part of it.
+pub fn heap_size(value: &Value) -> usize {
+    match value {
+        Value::Obj(Obj::String(string)) => string.len(),
+        Value::Obj(Obj::List(list)) => list.borrow().len() * size_of::<Value>(),
+        // Keys are stored once alongside their value and again in the index
+        Value::Obj(Obj::Map(map)) => {
+            map.borrow().len() * (3 * size_of::<Value>() + size_of::<usize>())
+        }
+        _ => 0,
+    }
+}
@@ -1,7 +1,17 @@
 mod errors;
+mod handler;
+mod index;
+mod iter;
+mod memory;
+mod output;
 mod stack;
 mod vm;
 
 pub use errors::*;
+pub use handler::*;
+pub use index::*;
+pub use iter::*;
+pub use memory::*;
+pub use output::*;
 pub use stack::*;
 pub use vm::*;
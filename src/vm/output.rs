@@ -0,0 +1,17 @@
+use std::io;
+use std::io::Write;
+
+/// Adapts a callback into an output sink. Each write is handed over as text, so a line may
+/// arrive in several pieces.
+pub struct OutputFn<F: FnMut(&str)>(pub F);
+
+impl<F: FnMut(&str)> Write for OutputFn<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (self.0)(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
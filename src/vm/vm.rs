@@ -1,8 +1,17 @@
-use crate::bytecode::{Chunk, GlobalMap, InternMap, Obj, Opcode, Value, LocalMap};
+use crate::bytecode::{
+    get_or_insert_string, Arity, BoundMethod, Chunk, ErrorObject, GlobalMap, InternMap, LocalMap,
+    Module, Native, NativeError, NativeFn, Obj, Opcode, Value,
+};
+use crate::compiler::{Compiler, Source};
+use crate::stdlib::{self, Capabilities, Capability, Rng};
 use crate::vm::errors::*;
+use crate::vm::{
+    build_map, build_range, get_index, get_slice, heap_size, iter_element, iter_sequence,
+    set_index, Handler,
+};
 
 use crate::vm::Stack;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::iter::Enumerate;
 use std::slice::Iter;
 
@@ -10,17 +19,62 @@ use std::slice::Iter;
 use crate::debug::Disassembler;
 #[cfg(feature = "trace_execution")]
 use crate::utils::PrettyPrinter;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 pub type VMResult = Result<(), RuntimeError>;
 
-pub struct VM<'chunk> {
-    chunk: &'chunk Chunk,
+/// Reading the clock on every instruction would slow down the dispatch loop noticeably
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+pub struct VM {
+    chunk: Chunk,
     ip: usize,
     stack: Stack,
+    /// Globals of the module that's running
     globals: GlobalMap,
+    /// Natives and constants every module can see, unless it defines a global of the same name
+    builtins: GlobalMap,
     strings: InternMap,
     locals: LocalMap,
+    capabilities: Capabilities,
+    /// Where `print` writes to
+    output: Box<dyn Write>,
+    /// Where `eprint` writes to
+    error_output: Box<dyn Write>,
+    /// Where execution traces go, kept apart from the script's own output
+    #[cfg_attr(not(feature = "trace_execution"), allow(dead_code))]
+    trace: Box<dyn Write>,
+    rng: Rng,
+    /// Command line arguments given after the script path
+    script_args: Vec<String>,
+    /// How many more instructions may run, if limited
+    instruction_budget: Option<u64>,
+    deadline: Option<Instant>,
+    executed: u64,
+    max_stack: Option<usize>,
+    max_heap_bytes: Option<usize>,
+    /// Estimated bytes of every object created since the last `load`, whether or not it's still
+    /// alive
+    allocated: usize,
+    /// The file the running code came from, which imports are resolved against
+    script_path: Option<PathBuf>,
+    /// Modules that finished loading, by canonical path, so each only runs once
+    modules: HashMap<PathBuf, Rc<Module>>,
+    /// Modules that are partway through loading, outermost first
+    importing: Vec<PathBuf>,
+    /// Modules that a limit stopped while they were loading, by canonical path
+    suspended: HashMap<PathBuf, ModuleRun>,
+    /// The `try` blocks the running code is inside, innermost last
+    handlers: Vec<Handler>,
+    /// The value being thrown by `throw`, until a handler picks it up
+    thrown: Option<Value>,
 
     #[cfg(feature = "trace_execution")]
     disassembler: Disassembler,
@@ -28,33 +82,231 @@ pub struct VM<'chunk> {
     offset: usize,
 }
 
-impl<'chunk> VM<'chunk> {
-    pub fn new(chunk: &'chunk Chunk, strings: InternMap, locals: LocalMap) -> VM<'chunk> {
-        VM {
+impl VM {
+    /// Creates a VM whose natives can only use `capabilities`, for running untrusted scripts.
+    pub(crate) fn with_capabilities(
+        chunk: Chunk,
+        strings: InternMap,
+        locals: LocalMap,
+        capabilities: Capabilities,
+    ) -> VM {
+        // Without the clock, seeding from the time would still leak it through `random`
+        let rng = if capabilities.allows(Capability::Clock) {
+            Rng::from_time()
+        } else {
+            Rng::new(0)
+        };
+        let mut vm = VM {
             chunk,
             ip: 0,
             stack: Stack::new(),
             globals: GlobalMap::new(),
+            builtins: GlobalMap::new(),
             strings,
             locals,
+            capabilities,
+            output: Box::new(io::stdout()),
+            error_output: Box::new(io::stderr()),
+            trace: Box::new(io::stdout()),
+            rng,
+            script_args: Vec::new(),
+            instruction_budget: None,
+            deadline: None,
+            executed: 0,
+            max_stack: None,
+            max_heap_bytes: None,
+            allocated: 0,
+            script_path: None,
+            modules: HashMap::new(),
+            importing: Vec::new(),
+            suspended: HashMap::new(),
+            handlers: Vec::new(),
+            thrown: None,
             #[cfg(feature = "trace_execution")]
             disassembler: Disassembler::new(),
             #[cfg(feature = "trace_execution")]
             offset: 0,
+        };
+        stdlib::register_natives(&mut vm);
+        vm
+    }
+
+    /// Replaces the code to run with `chunk`, keeping globals. The chunk has to have been
+    /// compiled with this VM's string table, from `strings_mut`.
+    pub(crate) fn load(&mut self, chunk: Chunk) {
+        self.chunk = chunk;
+        self.ip = 0;
+        self.stack.clear();
+        self.suspended.clear();
+        self.handlers.clear();
+        self.allocated = 0;
+        #[cfg(feature = "trace_execution")]
+        {
+            self.offset = 0;
+        }
+    }
+
+    /// The string intern table, for the compiler to share.
+    pub(crate) fn strings_mut(&mut self) -> &mut InternMap {
+        &mut self.strings
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(name).or_else(|| self.builtins.get(name))
+    }
+
+    pub(crate) fn define_global(&mut self, name: &str, value: Value) {
+        self.globals.insert(name.to_owned(), value);
+    }
+
+    /// Makes `function` callable from scripts as the global `name`.
+    pub(crate) fn define_native(&mut self, name: &str, arity: Arity, function: NativeFn) {
+        let native = Rc::new(Native::new(name, arity, function));
+        self.define_builtin(name, Value::Obj(Obj::Native(native)));
+    }
+
+    /// Defines `name` for every module, not just the main script.
+    pub(crate) fn define_builtin(&mut self, name: &str, value: Value) {
+        self.builtins.insert(name.to_owned(), value);
+    }
+
+    /// Sets the file the code being run came from. Imports are resolved relative to it, and
+    /// runtime errors name it.
+    pub(crate) fn set_script_path(&mut self, path: Option<PathBuf>) {
+        self.script_path = path;
+    }
+
+    pub(crate) fn script_path(&self) -> Option<&Path> {
+        self.script_path.as_deref()
+    }
+
+    pub(crate) fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub(crate) fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = output;
+    }
+
+    /// Sets where `eprint` writes to.
+    pub(crate) fn set_error_output(&mut self, error_output: Box<dyn Write>) {
+        self.error_output = error_output;
+    }
+
+    pub(crate) fn error_output(&mut self) -> &mut dyn Write {
+        &mut *self.error_output
+    }
+
+    /// Sets where the `trace_execution` feature writes its traces. Does nothing without it.
+    pub(crate) fn set_trace_output(&mut self, trace: Box<dyn Write>) {
+        self.trace = trace;
+    }
+
+    pub(crate) fn set_script_args(&mut self, script_args: Vec<String>) {
+        self.script_args = script_args;
+    }
+
+    pub(crate) fn script_args(&self) -> &[String] {
+        &self.script_args
+    }
+
+    /// The generator behind the `random` natives.
+    pub(crate) fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Limits how many more instructions can run before `interpret` stops with a
+    /// `BudgetExhausted` error, or removes the limit with `None`.
+    pub(crate) fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.instruction_budget = budget;
+    }
+
+    /// Makes `interpret` stop with a `Timeout` error once `deadline` has passed.
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.set_deadline(Some(Instant::now() + timeout));
+    }
+
+    /// Limits how many values the stack can hold before the script fails with a stack
+    /// overflow.
+    pub(crate) fn set_max_stack(&mut self, max_stack: Option<usize>) {
+        self.max_stack = max_stack;
+    }
+
+    /// Limits the total bytes of strings, lists and maps the script can create before it fails
+    /// with an out of memory error. Memory isn't given back when objects are dropped, so this
+    /// bounds everything a script allocates over its run. Each `load` starts a new run, with the
+    /// count back at zero.
+    pub(crate) fn set_max_heap_bytes(&mut self, max_heap_bytes: Option<usize>) {
+        self.max_heap_bytes = max_heap_bytes;
+    }
+
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.allocated
+    }
+
+    /// Counts `bytes` towards the heap limit, which is checked before the next instruction.
+    pub(crate) fn track_allocation(&mut self, bytes: usize) {
+        self.allocated = self.allocated.saturating_add(bytes);
+    }
+
+    pub(crate) fn instructions_executed(&self) -> u64 {
+        self.executed
+    }
+
+    /// Gives up on a script that was stopped by its budget or deadline, so that calling
+    /// `interpret` again just returns. Resuming instead is a matter of raising the limit and
+    /// calling `interpret` again.
+    pub(crate) fn abort(&mut self) {
+        self.stack.clear();
+        self.instruction_budget = None;
+        self.deadline = None;
+        self.suspended.clear();
+        self.handlers.clear();
+        // Every chunk ends with a RET
+        self.ip = self.chunk.code.len() - 1;
+    }
+
+    /// Runs until the script returns, fails or hits a limit. A limit stops it between
+    /// instructions, so calling this again resumes where it left off.
+    ///
+    /// Errors raised inside a `try` block are handed to its handler instead. Limits and `exit`
+    /// can't be caught.
+    pub(crate) fn interpret(&mut self) -> VMResult {
+        loop {
+            match self.run() {
+                Err(err) if err.kind == RuntimeErrorKind::Error && !self.handlers.is_empty() => {
+                    let handler = self.handlers.pop().unwrap();
+                    let exception = self.thrown.take().unwrap_or_else(|| {
+                        let error = ErrorObject::new(&err.message, err.line);
+                        Value::Obj(Obj::Error(Rc::new(error)))
+                    });
+                    self.stack.truncate(handler.stack_len);
+                    self.stack.push(exception);
+                    self.move_ip(handler.ip as i32 - self.ip as i32);
+                }
+                result => {
+                    self.thrown = None;
+                    return result;
+                }
+            }
         }
     }
 
-    pub fn interpret(&mut self) -> VMResult {
+    fn run(&mut self) -> VMResult {
         use Opcode::*;
 
         loop {
+            self.check_limits()?;
             #[cfg(feature = "trace_execution")]
             {
                 self.disassembler.print_stack(&self.stack);
                 self.disassembler
-                    .disassemble_instruction(self.chunk, self.offset);
-                println!("{}", self.disassembler.result());
-                self.disassembler.clear();
+                    .disassemble_instruction(&self.chunk, self.offset);
+                self.write_trace();
             }
             if let Some((line, instruction)) = self.read_byte() {
                 match instruction.try_into() {
@@ -64,8 +316,7 @@ impl<'chunk> VM<'chunk> {
                             {
                                 let ret = self.stack.pop().unwrap_or(Value::Nil);
                                 self.disassembler.print_value(&ret);
-                                println!("{}", self.disassembler.result());
-                                self.disassembler.clear();
+                                self.write_trace();
                             }
                             return Ok(());
                         }
@@ -74,25 +325,61 @@ impl<'chunk> VM<'chunk> {
                             self.stack.push(constant);
                         }
                         Neg => {
-                            let val: f64 = {
-                                match self.stack.last().unwrap() {
-                                    Value::Number(_) => {
-                                        self.stack.pop().unwrap().try_into().unwrap()
-                                    }
-                                    _ => {
-                                        return Err(RuntimeError::new(
-                                            line,
-                                            "Operand must be a number",
-                                        ));
-                                    }
+                            let val = match self.stack.pop().unwrap() {
+                                Value::Int(num) => num.checked_neg().map(Value::Int),
+                                Value::Number(num) => Some(Value::Number(-num)),
+                                _ => {
+                                    return Err(RuntimeError::new(
+                                        line,
+                                        "Operand must be a number",
+                                    ));
                                 }
                             };
-                            self.stack.push(Value::Number(val));
+                            match val {
+                                Some(val) => self.stack.push(val),
+                                None => return Err(RuntimeError::new(line, "Integer overflow")),
+                            }
                         }
-                        Add => self.add()?,
-                        Sub => self.binary_op(|left, right| Value::Number(left - right))?,
-                        Mul => self.binary_op(|left, right| Value::Number(left * right))?,
-                        Div => self.binary_op(|left, right| Value::Number(left / right))?,
+                        Add => self.add(line)?,
+                        Sub => self.binary_op(
+                            line,
+                            |left, right| checked(left.checked_sub(right)),
+                            |left, right| Value::Number(left - right),
+                        )?,
+                        Mul => self.binary_op(
+                            line,
+                            |left, right| checked(left.checked_mul(right)),
+                            |left, right| Value::Number(left * right),
+                        )?,
+                        Div => self.binary_op(
+                            line,
+                            |left, right| Ok(Value::Number(left as f64 / right as f64)),
+                            |left, right| Value::Number(left / right),
+                        )?,
+                        Mod => self.binary_op(line, int_modulo, |left, right| {
+                            Value::Number(left - right * (left / right).floor())
+                        })?,
+                        Pow => self.binary_op(line, int_pow, |left, right| {
+                            Value::Number(left.powf(right))
+                        })?,
+                        FloorDiv => self.binary_op(line, int_floor_div, |left, right| {
+                            Value::Number((left / right).floor())
+                        })?,
+                        BitAnd => self.bitwise_op(line, |left, right| Ok(left & right))?,
+                        BitOr => self.bitwise_op(line, |left, right| Ok(left | right))?,
+                        BitXor => self.bitwise_op(line, |left, right| Ok(left ^ right))?,
+                        BitNot => match self.stack.pop().unwrap() {
+                            Value::Int(val) => self.stack.push(Value::Int(!val)),
+                            _ => {
+                                return Err(RuntimeError::new(line, "Operand must be an integer"));
+                            }
+                        },
+                        Shl => self.bitwise_op(line, |left, right| {
+                            shift_amount(right).map(|right| left.wrapping_shl(right))
+                        })?,
+                        Shr => self.bitwise_op(line, |left, right| {
+                            shift_amount(right).map(|right| left >> right)
+                        })?,
                         True => self.stack.push(Value::Bool(true)),
                         False => self.stack.push(Value::Bool(false)),
                         Nil => self.stack.push(Value::Nil),
@@ -105,21 +392,50 @@ impl<'chunk> VM<'chunk> {
                             let b = self.stack.pop().unwrap();
                             self.stack.push(Value::Bool(a == b))
                         }
-                        Gt => self.binary_op(|left, right| Value::Bool(left > right))?,
-                        Lt => self.binary_op(|left, right| Value::Bool(left < right))?,
+                        Ne => {
+                            let a = self.stack.pop().unwrap();
+                            let b = self.stack.pop().unwrap();
+                            self.stack.push(Value::Bool(a != b))
+                        }
+                        Gt => self.binary_op(
+                            line,
+                            |left, right| Ok(Value::Bool(left > right)),
+                            |left, right| Value::Bool(left > right),
+                        )?,
+                        Ge => self.binary_op(
+                            line,
+                            |left, right| Ok(Value::Bool(left >= right)),
+                            |left, right| Value::Bool(left >= right),
+                        )?,
+                        Lt => self.binary_op(
+                            line,
+                            |left, right| Ok(Value::Bool(left < right)),
+                            |left, right| Value::Bool(left < right),
+                        )?,
+                        Le => self.binary_op(
+                            line,
+                            |left, right| Ok(Value::Bool(left <= right)),
+                            |left, right| Value::Bool(left <= right),
+                        )?,
+                        Stringify => {
+                            let value = self.stack.pop().unwrap();
+                            let string = match value {
+                                Value::Obj(Obj::String(_)) => value,
+                                value => self.new_string(&value.to_string()),
+                            };
+                            self.stack.push(string);
+                        }
                         Print => {
                             let value = self.stack.pop().unwrap();
-                            let mut debug = false;
                             #[cfg(feature = "trace_execution")]
                             {
-                                debug = true;
-                                PrettyPrinter::new(String::new())
-                                .print_print(&value)
-                                .print();
-                            }
-                            if !debug {
-                                println!("{}", &value);
+                                let mut printer = PrettyPrinter::new(String::new());
+                                printer.print_print(&value);
+                                let _ = write!(self.trace, "{}", printer.result());
                             }
+                            writeln!(self.output, "{}", &value).map_err(|err| {
+                                RuntimeError::new(line, &format!("Couldn't print: {}", err))
+                            })?;
                         }
                         Pop => {
                             self.stack.pop().unwrap();
@@ -130,7 +446,7 @@ impl<'chunk> VM<'chunk> {
                         }
                         GetGlobal => {
                             let name = self.read_string().unwrap();
-                            if let Some(value) = self.globals.get(name.as_ref()) {
+                            if let Some(value) = self.get_global(name.as_ref()) {
                                 self.stack.push(value.clone());
                             } else {
                                 return Err(RuntimeError::new(
@@ -157,13 +473,15 @@ impl<'chunk> VM<'chunk> {
                             }
                         }
                         GetLocal => {
-                            if let Some((_line, offset)) = self.read_byte() {
-                                self.stack.push(self.stack[offset as usize].clone())
+                            if let Some((line, offset)) = self.read_byte() {
+                                let slot = self.local_slot(line, offset)?;
+                                self.stack.push(self.stack[slot].clone())
                             }
                         }
                         SetLocal => {
-                            if let Some((_line, offset)) = self.read_byte() {
-                                self.stack[offset as usize] = self.stack.last().unwrap().clone();
+                            if let Some((line, offset)) = self.read_byte() {
+                                let slot = self.local_slot(line, offset)?;
+                                self.stack[slot] = self.stack.last().unwrap().clone();
                             }
                         }
                         JZ => {
@@ -183,6 +501,144 @@ impl<'chunk> VM<'chunk> {
                                 self.move_ip(-(offset as i32));
                             }
                         }
+                        JumpTable => {
+                            let min = self.read_constant();
+                            let (_line, count) = self.read_byte().unwrap();
+                            let table_start = self.ip;
+                            self.move_ip(2 * count as i32);
+
+                            let index = match (self.stack.pop().unwrap(), min) {
+                                (Value::Int(num), Value::Int(min)) => num.checked_sub(min),
+                                // Whole floats compare equal to ints, so they take the same case
+                                (Value::Number(num), Value::Int(min)) if num.fract() == 0.0 => {
+                                    (num as i64).checked_sub(min)
+                                }
+                                _ => None,
+                            };
+                            // Values without an entry fall through to the instruction after the table
+                            let index = index.filter(|index| (0..count as i64).contains(index));
+                            if let Some(index) = index {
+                                let entry = table_start + 2 * index as usize;
+                                let offset = ((self.chunk.code[entry] as u16) << 8)
+                                    + self.chunk.code[entry + 1] as u16;
+                                self.move_ip(-(offset as i32));
+                            }
+                        }
+                        BuildList => {
+                            let (_line, count) = self.read_byte().unwrap();
+                            let elements = self.stack.split_off(self.stack.len() - count as usize);
+                            let list = self.new_list(elements);
+                            self.stack.push(list);
+                        }
+                        BuildMap => {
+                            let (_line, count) = self.read_byte().unwrap();
+                            let entries =
+                                self.stack.split_off(self.stack.len() - 2 * count as usize);
+                            let map = build_map(entries)
+                                .map_err(|message| RuntimeError::new(line, &message))?;
+                            let map = Value::Obj(Obj::new_map(map));
+                            self.track_allocation(heap_size(&map));
+                            self.stack.push(map);
+                        }
+                        BuildRange => {
+                            let end = self.stack.pop().unwrap();
+                            let start = self.stack.pop().unwrap();
+                            let range = build_range(start, end)
+                                .map_err(|message| RuntimeError::new(line, &message))?;
+                            self.stack.push(range);
+                        }
+                        GetIndex => {
+                            let index = self.stack.pop().unwrap();
+                            let target = self.stack.pop().unwrap();
+                            let value = get_index(&target, &index, &mut self.strings)
+                                .map_err(|message| RuntimeError::new(line, &message))?;
+                            self.stack.push(value);
+                        }
+                        SetIndex => {
+                            let value = self.stack.pop().unwrap();
+                            let index = self.stack.pop().unwrap();
+                            let target = self.stack.pop().unwrap();
+                            let size_before = heap_size(&target);
+                            set_index(&target, &index, value.clone())
+                                .map_err(|message| RuntimeError::new(line, &message))?;
+                            // Only new map entries take up more space
+                            self.track_allocation(heap_size(&target).saturating_sub(size_before));
+                            self.stack.push(value);
+                        }
+                        GetSlice => {
+                            let end = self.stack.pop().unwrap();
+                            let start = self.stack.pop().unwrap();
+                            let target = self.stack.pop().unwrap();
+                            let slice = get_slice(&target, &start, &end, &mut self.strings)
+                                .map_err(|message| RuntimeError::new(line, &message))?;
+                            self.track_allocation(heap_size(&slice));
+                            self.stack.push(slice);
+                        }
+                        IterInit => {
+                            let value = self.stack.pop().unwrap();
+                            let is_copy = matches!(value, Value::Obj(Obj::String(_) | Obj::Map(_)));
+                            let sequence = iter_sequence(value, &mut self.strings)
+                                .map_err(|message| RuntimeError::new(line, &message))?;
+                            if is_copy {
+                                self.track_allocation(heap_size(&sequence));
+                            }
+                            self.stack.push(sequence);
+                            self.stack.push(Value::Int(0));
+                        }
+                        IterNext => {
+                            // The sequence is in `slot` and the index of the next element is
+                            // in the slot after it
+                            let (_line, slot) = self.read_byte().unwrap();
+                            let (_line, offset) = self.read_short().unwrap();
+                            let slot = slot as usize;
+                            let index = match self.stack[slot + 1] {
+                                Value::Int(index) => index as usize,
+                                _ => unreachable!("ITER_NEXT index slot always holds an int"),
+                            };
+                            match iter_element(&self.stack[slot], index) {
+                                Some(element) => {
+                                    self.stack[slot + 1] = Value::Int(index as i64 + 1);
+                                    self.stack.push(element);
+                                }
+                                None => self.move_ip(offset as i32),
+                            }
+                        }
+                        Call => {
+                            let (_line, arg_count) = self.read_byte().unwrap();
+                            self.call_value(line, arg_count as usize)?;
+                        }
+                        Import => {
+                            let start = self.ip - 1;
+                            let path = self.read_string().unwrap();
+                            match self.import(line, &path) {
+                                Ok(module) => self.stack.push(Value::Obj(Obj::Module(module))),
+                                Err(err) => {
+                                    // Resuming after a limit picks the import up where the module stopped
+                                    self.move_ip(start as i32 - self.ip as i32);
+                                    return Err(err);
+                                }
+                            }
+                        }
+                        PushHandler => {
+                            let (_line, offset) = self.read_short().unwrap();
+                            self.handlers.push(Handler {
+                                ip: self.ip + offset as usize,
+                                stack_len: self.stack.len(),
+                            });
+                        }
+                        PopHandler => {
+                            self.handlers.pop();
+                        }
+                        Throw => {
+                            let value = self.stack.pop().unwrap();
+                            return Err(self.throw(line, value));
+                        }
+                        GetProperty => {
+                            let name = self.read_string().unwrap();
+                            let receiver = self.stack.pop().unwrap();
+                            let property = self.get_property(line, receiver, &name)?;
+                            self.stack.push(property);
+                        }
                     },
                     Err(..) => {
                         panic!("Couldn't decode opcode {}", instruction);
@@ -194,61 +650,380 @@ impl<'chunk> VM<'chunk> {
         }
     }
 
-    fn binary_op<F>(&mut self, f: F) -> VMResult
+    /// Flushes the disassembler's pending output to the trace sink. A trace that can't be
+    /// written shouldn't stop the script, so errors are ignored.
+    #[cfg(feature = "trace_execution")]
+    fn write_trace(&mut self) {
+        let _ = writeln!(self.trace, "{}", self.disassembler.result());
+        self.disassembler.clear();
+    }
+
+    // u64::is_multiple_of needs Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    fn check_limits(&mut self) -> VMResult {
+        let line = self.chunk.lines[self.ip];
+        if let Some(deadline) = self.deadline {
+            if self.executed % DEADLINE_CHECK_INTERVAL == 0 && Instant::now() >= deadline {
+                return Err(RuntimeError::timeout(line));
+            }
+        }
+        if matches!(self.max_stack, Some(max) if self.stack.len() > max) {
+            return Err(RuntimeError::stack_overflow(line));
+        }
+        if matches!(self.max_heap_bytes, Some(max) if self.allocated > max) {
+            return Err(RuntimeError::out_of_memory(line));
+        }
+        if let Some(budget) = self.instruction_budget {
+            if budget == 0 {
+                return Err(RuntimeError::budget_exhausted(line));
+            }
+            self.instruction_budget = Some(budget - 1);
+        }
+        self.executed += 1;
+        Ok(())
+    }
+
+    /// Checks that a local's slot is on the stack, so bad bytecode fails the script rather than
+    /// the host.
+    fn local_slot(&self, line: usize, offset: u8) -> Result<usize, RuntimeError> {
+        let slot = offset as usize;
+        if slot < self.stack.len() {
+            Ok(slot)
+        } else {
+            Err(RuntimeError::new(
+                line,
+                &format!("Local variable slot {} is past the end of the stack", slot),
+            ))
+        }
+    }
+
+    /// Applies an arithmetic or comparison operator, using `int_op` when both operands are ints
+    /// and promoting to floats otherwise.
+    fn binary_op<I, F>(&mut self, line: usize, int_op: I, float_op: F) -> VMResult
     where
+        I: FnOnce(i64, i64) -> Result<Value, &'static str>,
         F: FnOnce(f64, f64) -> Value,
     {
         match (self.stack.pop(), self.stack.pop()) {
-            (Some(Value::Number(right)), Some(Value::Number(left))) => {
-                self.stack.push(f(left, right));
+            (Some(right), Some(left)) => {
+                let result = numeric_op(left, right, int_op, float_op)
+                    .map_err(|message| RuntimeError::new(line, message))?;
+                self.stack.push(result);
                 Ok(())
             }
-            (Some(_), Some(_)) => {
-                return Err(RuntimeError::new(0, "Expected two numbers on the stack"));
+            (None, _) | (_, None) => {
+                Err(RuntimeError::new(
+                    line,
+                    "Expected at least two items on the stack",
+                ))
+            }
+        }
+    }
+
+    fn bitwise_op<F>(&mut self, line: usize, f: F) -> VMResult
+    where
+        F: FnOnce(i64, i64) -> Result<i64, &'static str>,
+    {
+        match (self.stack.pop(), self.stack.pop()) {
+            (Some(Value::Int(right)), Some(Value::Int(left))) => {
+                let result = f(left, right).map_err(|message| RuntimeError::new(line, message))?;
+                self.stack.push(Value::Int(result));
+                Ok(())
             }
+            (Some(_), Some(_)) => Err(RuntimeError::new(line, "Operands must be integers")),
             (None, _) | (_, None) => {
-                return Err(RuntimeError::new(
-                    0,
+                Err(RuntimeError::new(
+                    line,
                     "Expected at least two items on the stack",
-                ));
+                ))
             }
         }
     }
 
-    fn add(&mut self) -> VMResult {
+    fn add(&mut self, line: usize) -> VMResult {
         match (self.stack.pop(), self.stack.pop()) {
-            (Some(Value::Number(left)), Some(Value::Number(right))) => {
-                self.stack.push(Value::Number(left + right));
+            (Some(right), Some(left)) if left.is_number() && right.is_number() => {
+                let result = numeric_op(
+                    left,
+                    right,
+                    |left, right| checked(left.checked_add(right)),
+                    |left, right| Value::Number(left + right),
+                )
+                .map_err(|message| RuntimeError::new(line, message))?;
+                self.stack.push(result);
                 Ok(())
             }
             (Some(Value::Obj(Obj::String(second))), Some(Value::Obj(Obj::String(first)))) => {
                 self.concatenate_strings(first, second)
             }
             (Some(_), Some(_)) => {
-                return Err(RuntimeError::new(
-                    0,
+                Err(RuntimeError::new(
+                    line,
                     "Expected two numbers or two strings on the stack",
-                ));
+                ))
             }
             (None, _) | (_, None) => {
-                return Err(RuntimeError::new(
-                    0,
+                Err(RuntimeError::new(
+                    line,
                     "Expected at least two items on the stack",
-                ));
+                ))
+            }
+        }
+    }
+
+    /// Interns `string`, for natives that build new strings.
+    pub fn new_string(&mut self, string: &str) -> Value {
+        self.track_allocation(string.len());
+        Value::Obj(Obj::String(get_or_insert_string(string, &mut self.strings)))
+    }
+
+    pub fn new_list(&mut self, values: Vec<Value>) -> Value {
+        let list = Value::Obj(Obj::new_list(values));
+        self.track_allocation(heap_size(&list));
+        list
+    }
+
+    /// Calls the callee sitting below the top `arg_count` values, replacing it and its arguments
+    /// with the result.
+    fn call_value(&mut self, line: usize, arg_count: usize) -> VMResult {
+        let callee = self.stack[self.stack.len() - 1 - arg_count].clone();
+        match callee {
+            Value::Obj(Obj::Native(native)) => {
+                if !native.arity.accepts(arg_count) {
+                    return Err(RuntimeError::new(
+                        line,
+                        &format!(
+                            "{}() expects {} arguments but got {}",
+                            native.name, native.arity, arg_count
+                        ),
+                    ));
+                }
+                let args = self.stack.split_off(self.stack.len() - arg_count);
+                self.stack.pop();
+                let result =
+                    (native.function)(self, &args).map_err(|err| native_error(line, err))?;
+                self.stack.push(result);
+                Ok(())
+            }
+            Value::Obj(Obj::BoundMethod(bound)) => {
+                let class = bound.receiver.class.clone();
+                let method = class.get_method(&bound.name).unwrap();
+                if !method.arity.accepts(arg_count) {
+                    return Err(RuntimeError::new(
+                        line,
+                        &format!(
+                            "{}.{}() expects {} arguments but got {}",
+                            class.name, bound.name, method.arity, arg_count
+                        ),
+                    ));
+                }
+                let args = self.stack.split_off(self.stack.len() - arg_count);
+                self.stack.pop();
+                let result = (method.function)(self, &bound.receiver, &args)
+                    .map_err(|err| native_error(line, err))?;
+                self.stack.push(result);
+                Ok(())
+            }
+            other => Err(RuntimeError::new(
+                line,
+                &format!("Can only call functions, not {}", other.type_name()),
+            )),
+        }
+    }
+
+    /// Loads the module at `path`, relative to the running file, or returns it from the cache.
+    fn import(&mut self, line: usize, path: &str) -> Result<Rc<Module>, RuntimeError> {
+        // The main script counts as being imported too, so importing it back is a cycle
+        let outermost = self.importing.is_empty();
+        if outermost {
+            let script_path = self
+                .script_path
+                .as_ref()
+                .and_then(|p| p.canonicalize().ok());
+            self.importing.extend(script_path);
+        }
+        let result = self.load_module(line, path);
+        if outermost {
+            self.importing.clear();
+        }
+        result
+    }
+
+    fn load_module(&mut self, line: usize, path: &str) -> Result<Rc<Module>, RuntimeError> {
+        if !self.capabilities.allows(Capability::Filesystem) {
+            return Err(RuntimeError::new(
+                line,
+                "This script isn't allowed to import modules",
+            ));
+        }
+
+        let base = match &self.script_path {
+            Some(script_path) => script_path.parent().unwrap_or_else(|| Path::new("")),
+            None => Path::new(""),
+        };
+        let mut full_path = base.join(path);
+        if full_path.extension().is_none() {
+            full_path.set_extension("lox");
+        }
+        let full_path = full_path.canonicalize().map_err(|err| {
+            RuntimeError::new(line, &format!("Couldn't import '{}': {}", path, err))
+        })?;
+
+        if let Some(module) = self.modules.get(&full_path) {
+            return Ok(module.clone());
+        }
+        if let Some(start) = self.importing.iter().position(|p| *p == full_path) {
+            let cycle: Vec<String> = self.importing[start..]
+                .iter()
+                .chain(std::iter::once(&full_path))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(RuntimeError::new(
+                line,
+                &format!("Import cycle: {}", cycle.join(" -> ")),
+            ));
+        }
+
+        let run = match self.suspended.remove(&full_path) {
+            Some(run) => run,
+            None => self.compile_module(line, path, &full_path)?,
+        };
+        self.importing.push(full_path.clone());
+        let result = self.run_module(run, &full_path);
+        self.importing.pop();
+
+        let module = Rc::new(Module {
+            name: module_name(&full_path),
+            path: full_path.clone(),
+            globals: result?,
+        });
+        self.modules.insert(full_path, module.clone());
+        Ok(module)
+    }
+
+    fn compile_module(
+        &mut self,
+        line: usize,
+        path: &str,
+        full_path: &Path,
+    ) -> Result<ModuleRun, RuntimeError> {
+        let source = fs::read_to_string(full_path).map_err(|err| {
+            RuntimeError::new(line, &format!("Couldn't import '{}': {}", path, err))
+        })?;
+        let compiler = Compiler::with_strings(Source::new(&source), &mut self.strings);
+        match compiler.compile() {
+            Ok((chunk, _locals)) => Ok(ModuleRun::new(chunk)),
+            Err(err) => {
+                let first = &err.errors[0];
+                let message = match &first.location {
+                    Some(location) => format!("Error {}: {}", location, first.message),
+                    None => format!("Error: {}", first.message),
+                };
+                Err(RuntimeError::new(first.line, &message).in_file(full_path))
             }
         }
     }
 
+    /// Runs a module to the end with its own globals, then puts back the code that was running
+    /// and hands over the globals the module defined. If a limit stops the module, its state is
+    /// kept so that resuming carries on from where it stopped instead of running it again.
+    fn run_module(&mut self, run: ModuleRun, path: &Path) -> Result<GlobalMap, RuntimeError> {
+        let chunk = mem::replace(&mut self.chunk, run.chunk);
+        let ip = mem::replace(&mut self.ip, run.ip);
+        let globals = mem::replace(&mut self.globals, run.globals);
+        let script_path = self.script_path.replace(path.to_path_buf());
+        let handlers = mem::replace(&mut self.handlers, run.handlers);
+        #[cfg(feature = "trace_execution")]
+        let offset = mem::replace(&mut self.offset, run.offset);
+
+        let result = self.interpret();
+
+        let stopped = ModuleRun {
+            chunk: mem::replace(&mut self.chunk, chunk),
+            ip: mem::replace(&mut self.ip, ip),
+            globals: mem::replace(&mut self.globals, globals),
+            handlers: mem::replace(&mut self.handlers, handlers),
+            #[cfg(feature = "trace_execution")]
+            offset: mem::replace(&mut self.offset, offset),
+        };
+        self.script_path = script_path;
+        match result {
+            Ok(()) => Ok(stopped.globals),
+            Err(err) => {
+                if err.kind.is_limit() {
+                    self.suspended.insert(path.to_path_buf(), stopped);
+                }
+                Err(err.in_file(path))
+            }
+        }
+    }
+
+    /// Makes the error that carries a thrown value up to its handler. If nothing catches it,
+    /// the error describes the value instead.
+    fn throw(&mut self, line: usize, value: Value) -> RuntimeError {
+        let err = match &value {
+            Value::Obj(Obj::Error(error)) => RuntimeError::new(error.line, &error.message),
+            value => RuntimeError::new(line, &format!("Uncaught exception: {}", value)),
+        };
+        self.thrown = Some(value);
+        err
+    }
+
+    /// Reads `name` from a foreign object, running its getter or binding its method.
+    fn get_property(
+        &mut self,
+        line: usize,
+        receiver: Value,
+        name: &str,
+    ) -> Result<Value, RuntimeError> {
+        let foreign = match receiver {
+            Value::Obj(Obj::Foreign(foreign)) => foreign,
+            Value::Obj(Obj::Error(error)) => {
+                return match name {
+                    "message" => Ok(self.new_string(&error.message)),
+                    "line" => Ok(Value::Int(error.line as i64)),
+                    _ => Err(RuntimeError::new(
+                        line,
+                        &format!("Errors have no property '{}'", name),
+                    )),
+                }
+            }
+            Value::Obj(Obj::Module(module)) => {
+                return module.globals.get(name).cloned().ok_or_else(|| {
+                    RuntimeError::new(
+                        line,
+                        &format!("Module '{}' has no global '{}'", module.name, name),
+                    )
+                })
+            }
+            other => {
+                return Err(RuntimeError::new(
+                    line,
+                    &format!("Values of type {} don't have properties", other.type_name()),
+                ))
+            }
+        };
+
+        if let Some(getter) = foreign.class.get_getter(name) {
+            getter(self, &foreign).map_err(|err| native_error(line, err))
+        } else if foreign.class.get_method(name).is_some() {
+            let bound = BoundMethod {
+                receiver: foreign,
+                name: name.to_owned(),
+            };
+            Ok(Value::Obj(Obj::BoundMethod(Rc::new(bound))))
+        } else {
+            Err(RuntimeError::new(
+                line,
+                &format!("{} has no property '{}'", foreign.class.name, name),
+            ))
+        }
+    }
+
     fn concatenate_strings(&mut self, first: Rc<String>, second: Rc<String>) -> VMResult {
         let concat = format!("{}{}", &first, &second);
-        let rc_concat = if let Some(string) = self.strings.get(&concat) {
-            string.clone()
-        } else {
-            let rc = Rc::new(concat.clone());
-            self.strings.insert(concat, rc.clone());
-            rc
-        };
-        self.stack.push(Value::Obj(Obj::String(rc_concat)));
+        let concat = self.new_string(&concat);
+        self.stack.push(concat);
         Ok(())
     }
 
@@ -263,7 +1038,7 @@ impl<'chunk> VM<'chunk> {
         {
             self.offset += 1;
         }
-        let ret = Some((self.ip, self.chunk.code[self.ip]));
+        let ret = Some((self.chunk.lines[self.ip], self.chunk.code[self.ip]));
         self.ip += 1;
         ret
     }
@@ -290,3 +1065,240 @@ impl<'chunk> VM<'chunk> {
         self.ip = (self.ip as i32 + offset) as usize;
     }
 }
+
+pub fn numeric_op<I, F>(
+    left: Value,
+    right: Value,
+    int_op: I,
+    float_op: F,
+) -> Result<Value, &'static str>
+where
+    I: FnOnce(i64, i64) -> Result<Value, &'static str>,
+    F: FnOnce(f64, f64) -> Value,
+{
+    match (left, right) {
+        (Value::Int(left), Value::Int(right)) => int_op(left, right),
+        (left, right) => match (f64::try_from(left), f64::try_from(right)) {
+            (Ok(left), Ok(right)) => Ok(float_op(left, right)),
+            _ => Err("Expected two numbers on the stack"),
+        },
+    }
+}
+
+fn checked(result: Option<i64>) -> Result<Value, &'static str> {
+    result.map(Value::Int).ok_or("Integer overflow")
+}
+
+/// Modulo rounding towards negative infinity, so the result takes the sign of the divisor.
+fn int_modulo(left: i64, right: i64) -> Result<Value, &'static str> {
+    if right == 0 {
+        return Err("Division by zero");
+    }
+    let rem = left.wrapping_rem(right);
+    if rem != 0 && (rem < 0) != (right < 0) {
+        Ok(Value::Int(rem + right))
+    } else {
+        Ok(Value::Int(rem))
+    }
+}
+
+fn int_floor_div(left: i64, right: i64) -> Result<Value, &'static str> {
+    if right == 0 {
+        return Err("Division by zero");
+    }
+    let quotient = left.checked_div(right).ok_or("Integer overflow")?;
+    if left.wrapping_rem(right) != 0 && (left < 0) != (right < 0) {
+        Ok(Value::Int(quotient - 1))
+    } else {
+        Ok(Value::Int(quotient))
+    }
+}
+
+/// Negative exponents can't produce an int, so those fall back to floats.
+pub fn int_pow(left: i64, right: i64) -> Result<Value, &'static str> {
+    if right < 0 {
+        return Ok(Value::Number((left as f64).powf(right as f64)));
+    }
+    let exponent = u32::try_from(right).map_err(|_| "Integer overflow")?;
+    checked(left.checked_pow(exponent))
+}
+
+fn shift_amount(amount: i64) -> Result<u32, &'static str> {
+    if (0..64).contains(&amount) {
+        Ok(amount as u32)
+    } else {
+        Err("Shift amount must be between 0 and 63")
+    }
+}
+
+/// Where a module's code has got to, so it can be set aside while an import is stopped.
+struct ModuleRun {
+    chunk: Chunk,
+    ip: usize,
+    globals: GlobalMap,
+    handlers: Vec<Handler>,
+    #[cfg(feature = "trace_execution")]
+    offset: usize,
+}
+
+impl ModuleRun {
+    fn new(chunk: Chunk) -> ModuleRun {
+        ModuleRun {
+            chunk,
+            ip: 0,
+            globals: GlobalMap::new(),
+            handlers: Vec::new(),
+            #[cfg(feature = "trace_execution")]
+            offset: 0,
+        }
+    }
+}
+
+fn module_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn native_error(line: usize, err: NativeError) -> RuntimeError {
+    match err {
+        NativeError::Error(message) => RuntimeError::new(line, &message),
+        NativeError::Exit(code) => RuntimeError::exit(line, code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::{Compiler, Source};
+
+    fn compiled_vm(source: &str) -> VM {
+        let mut strings = InternMap::new();
+        match Compiler::with_strings(Source::new(source), &mut strings).compile() {
+            Ok((chunk, locals)) => {
+                VM::with_capabilities(chunk, strings, locals, Capabilities::all())
+            }
+            Err(_) => panic!("{} should have compiled", source),
+        }
+    }
+
+    fn run_code(code: &[u8]) -> VMResult {
+        let mut chunk = Chunk::new();
+        for &byte in code {
+            chunk.write(byte, 1);
+        }
+        let mut vm = VM::with_capabilities(
+            chunk,
+            InternMap::new(),
+            LocalMap::new(),
+            Capabilities::all(),
+        );
+        vm.interpret()
+    }
+
+    #[test]
+    fn budget_stops_infinite_loops() {
+        let mut vm = compiled_vm("while (true) {}");
+        vm.set_instruction_budget(Some(1000));
+        let err = vm.interpret().unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::BudgetExhausted));
+        assert_eq!(err.message, "Instruction budget exhausted");
+        assert_eq!(vm.instructions_executed(), 1000);
+    }
+
+    #[test]
+    fn budget_can_be_raised_to_resume() {
+        let mut vm = compiled_vm("let i = 0; while (i < 100) { i = i + 1; }");
+        vm.set_instruction_budget(Some(10));
+        let err = vm.interpret().unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::BudgetExhausted));
+        assert_ne!(vm.globals.get("i"), Some(&Value::Int(100)));
+
+        vm.set_instruction_budget(None);
+        assert!(vm.interpret().is_ok());
+        assert_eq!(vm.globals.get("i"), Some(&Value::Int(100)));
+    }
+
+    #[test]
+    fn abort_gives_up_on_a_stopped_script() {
+        let mut vm = compiled_vm("let done = false; while (!done) {} done = true;");
+        vm.set_instruction_budget(Some(10));
+        assert!(vm.interpret().is_err());
+
+        vm.abort();
+        assert!(vm.interpret().is_ok());
+        assert_eq!(vm.globals.get("done"), Some(&Value::Bool(false)));
+    }
+
+    #[test]
+    fn budget_is_enough_for_short_scripts() {
+        let mut vm = compiled_vm("let x = 1 + 2;");
+        vm.set_instruction_budget(Some(1000));
+        assert!(vm.interpret().is_ok());
+        assert_eq!(vm.globals.get("x"), Some(&Value::Int(3)));
+    }
+
+    #[test]
+    fn timeout_stops_infinite_loops() {
+        let mut vm = compiled_vm("while (true) {}");
+        vm.set_timeout(Duration::from_millis(50));
+        let start = Instant::now();
+        let err = vm.interpret().unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::Timeout));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn max_stack() {
+        let mut vm = compiled_vm("{ let a = 1; let b = 2; let c = a + b; }");
+        vm.set_max_stack(Some(4));
+        assert!(vm.interpret().is_ok());
+
+        let source = "{ let a = 1; let b = 2; let c = 3; let d = 4; let e = 5; let f = 6; }";
+        let mut vm = compiled_vm(source);
+        vm.set_max_stack(Some(4));
+        let err = vm.interpret().unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::StackOverflow));
+        assert_eq!(err.message, "Stack overflow");
+    }
+
+    #[test]
+    fn max_heap_bytes() {
+        let mut vm = compiled_vm(r#"let s = "x"; while (true) { s = s + s; }"#);
+        vm.set_max_heap_bytes(Some(10_000));
+        let err = vm.interpret().unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::OutOfMemory));
+        assert_eq!(err.message, "Out of memory");
+        assert!(vm.allocated_bytes() > 10_000);
+    }
+
+    #[test]
+    fn max_heap_bytes_counts_lists() {
+        let source = "let xs = []; while (true) { push(xs, [1, 2, 3]); }";
+        let mut vm = compiled_vm(source);
+        vm.set_max_heap_bytes(Some(10_000));
+        let err = vm.interpret().unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::OutOfMemory));
+    }
+
+    #[test]
+    fn local_slots_past_the_stack_are_errors() {
+        let err = run_code(&[Opcode::GetLocal as u8, 3, Opcode::Ret as u8]).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Local variable slot 3 is past the end of the stack"
+        );
+
+        let code = [
+            Opcode::Nil as u8,
+            Opcode::SetLocal as u8,
+            1,
+            Opcode::Ret as u8,
+        ];
+        let err = run_code(&code).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Local variable slot 1 is past the end of the stack"
+        );
+    }
+}
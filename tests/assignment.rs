@@ -0,0 +1,61 @@
+mod common;
+
+use common::{run, runtime_error, syntax_errors};
+
+#[test]
+fn compound_assignment_on_globals() {
+    let source = "let x = 1; x += 2; print x; x -= 5; print x; x *= 3; print x; x /= 4; print x;";
+    assert_eq!(run(source), "3\n-2\n-6\n-1.5\n");
+}
+
+#[test]
+fn compound_assignment_on_locals() {
+    let source = "{ let x = 10; x += 1; print x; x *= 2; print x; }";
+    assert_eq!(run(source), "11\n22\n");
+}
+
+#[test]
+fn plus_equal_concatenates_strings() {
+    assert_eq!(run("let s = \"a\"; s += \"b\"; print s;"), "ab\n");
+}
+
+#[test]
+fn compound_assignment_is_an_expression() {
+    assert_eq!(run("let x = 1; print x += 4; print x;"), "5\n5\n");
+}
+
+#[test]
+fn prefix_increment_returns_new_value() {
+    assert_eq!(
+        run("let x = 1; print ++x; print --x; print x;"),
+        "2\n1\n1\n"
+    );
+    assert_eq!(run("{ let x = 1; print ++x; print x; }"), "2\n2\n");
+}
+
+#[test]
+fn postfix_increment_returns_old_value() {
+    assert_eq!(
+        run("let x = 1; print x++; print x--; print x;"),
+        "1\n2\n1\n"
+    );
+    assert_eq!(run("{ let x = 1; print x++; print x; }"), "1\n2\n");
+}
+
+#[test]
+fn increment_works_on_floats() {
+    assert_eq!(run("let x = 1.5; x++; print x;"), "2.5\n");
+}
+
+#[test]
+fn compound_assignment_to_undefined_global_fails() {
+    assert_eq!(
+        runtime_error("y += 1;"),
+        "Tried to get value of undefined variable 'y'"
+    );
+}
+
+#[test]
+fn compound_assignment_needs_a_variable() {
+    assert_eq!(syntax_errors("1 += 2;"), vec!["Invalid assignment target."]);
+}
@@ -0,0 +1,188 @@
+#![allow(dead_code)]
+
+use rlox::{InterpretError, Interpreter, RuntimeError};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a script may run before the test gives up on it.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Style the pretty printer paints error messages with.
+const ERROR_STYLE: &str = "\x1b[1;38;2;220;50;47m";
+const RESET: &str = "\x1b[0m";
+
+/// What running a script printed, with terminal colours removed.
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+    pub syntax_errors: Vec<String>,
+    pub runtime_errors: Vec<String>,
+    pub exit_code: Option<i32>,
+}
+
+/// Runs `source` with the `rlox` binary and collects what it printed.
+pub fn execute(source: &str) -> Output {
+    execute_with(source, &[], "")
+}
+
+/// Runs `source` with `args` after the script path and `stdin` as its standard input.
+pub fn execute_with(source: &str, args: &[&str], stdin: &str) -> Output {
+    let path = script_path();
+    fs::write(&path, source).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .arg(&path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let stdout = read_in_background(child.stdout.take().unwrap());
+    let stderr = read_in_background(child.stderr.take().unwrap());
+    let status = wait_or_kill(&mut child, source);
+    let stdout = stdout.join().unwrap();
+    let stderr = stderr.join().unwrap();
+    fs::remove_file(&path).ok();
+
+    let mut output = Output {
+        stdout: String::new(),
+        stderr,
+        syntax_errors: Vec::new(),
+        runtime_errors: Vec::new(),
+        exit_code: status.code(),
+    };
+    for line in stdout.split_inclusive('\n') {
+        match error_message(line) {
+            Some(message) if line.contains("Error") => output.syntax_errors.push(message),
+            Some(message) => output.runtime_errors.push(message),
+            None => output.stdout.push_str(&strip_colours(line)),
+        }
+    }
+    output
+}
+
+/// Runs `source` and returns what it printed, failing the test if it reported any errors.
+pub fn run(source: &str) -> String {
+    let output = execute(source);
+    if !output.syntax_errors.is_empty() || !output.runtime_errors.is_empty() {
+        panic!(
+            "{} failed: {:?}",
+            source,
+            [output.syntax_errors, output.runtime_errors].concat()
+        );
+    }
+    output.stdout
+}
+
+/// Runs `source`, expecting it to fail at runtime, and returns the error message.
+pub fn runtime_error(source: &str) -> String {
+    let mut output = execute(source);
+    if !output.syntax_errors.is_empty() {
+        panic!(
+            "{} should have compiled, but: {:?}",
+            source, output.syntax_errors
+        );
+    }
+    match output.runtime_errors.pop() {
+        Some(message) => message,
+        None => panic!("{} should have failed", source),
+    }
+}
+
+/// Compiles `source`, expecting syntax errors, and returns their messages.
+pub fn syntax_errors(source: &str) -> Vec<String> {
+    let output = execute(source);
+    if output.syntax_errors.is_empty() {
+        panic!("{} should have failed to compile", source);
+    }
+    output.syntax_errors
+}
+
+/// An interpreter for embedding tests, capturing what scripts print.
+pub fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.capture_output();
+    interpreter
+}
+
+/// The runtime error in `err`, failing the test if `source` didn't compile.
+pub fn expect_runtime_error(source: &str, err: InterpretError) -> RuntimeError {
+    match err {
+        InterpretError::RuntimeError(err) => err,
+        InterpretError::CompileError(err) => {
+            panic!("{} should have compiled, but: {}", source, err)
+        }
+    }
+}
+
+fn script_path() -> PathBuf {
+    static NEXT_SCRIPT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!("rlox-tests-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(format!(
+        "{}.lox",
+        NEXT_SCRIPT.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+fn read_in_background<R: Read + Send + 'static>(mut reader: R) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+fn wait_or_kill(child: &mut Child, source: &str) -> ExitStatus {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            return status;
+        }
+        if start.elapsed() > TIMEOUT {
+            child.kill().ok();
+            child.wait().ok();
+            panic!("{} didn't finish within {:?}", source, TIMEOUT);
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// The message of an error the pretty printer printed on `line`, if there is one.
+fn error_message(line: &str) -> Option<String> {
+    let start = line.find(ERROR_STYLE)? + ERROR_STYLE.len();
+    let end = start + line[start..].find(RESET)?;
+    Some(line[start..end].to_string())
+}
+
+fn strip_colours(line: &str) -> String {
+    let mut stripped = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// An empty directory for a test to put files in, unique to the test and this test run.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rlox-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::interpreter;
+use rlox::{ConversionError, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[test]
+fn scalars_round_trip() {
+    assert_eq!(bool::try_from(Value::from(true)), Ok(true));
+    assert_eq!(i64::try_from(Value::from(-7i64)), Ok(-7));
+    assert_eq!(f64::try_from(Value::from(1.5)), Ok(1.5));
+    assert_eq!(
+        String::try_from(Value::from("héllo")),
+        Ok("héllo".to_owned())
+    );
+}
+
+#[test]
+fn ints_widen_to_floats_but_not_back() {
+    assert_eq!(f64::try_from(Value::Int(3)), Ok(3.0));
+    assert_eq!(
+        i64::try_from(Value::Number(3.0)),
+        Err(ConversionError {
+            expected: "int",
+            found: "float"
+        })
+    );
+}
+
+#[test]
+fn options_use_nil() {
+    assert_eq!(Value::from(None::<i64>), Value::Nil);
+    assert_eq!(Value::from(Some(2i64)), Value::Int(2));
+    assert_eq!(Option::<String>::try_from(Value::Nil), Ok(None));
+    assert_eq!(Option::<i64>::try_from(Value::Int(2)), Ok(Some(2)));
+    assert_eq!(
+        Option::<bool>::try_from(Value::Int(2)),
+        Err(ConversionError {
+            expected: "bool",
+            found: "int"
+        })
+    );
+}
+
+#[test]
+fn lists() {
+    let value = Value::from(vec![1i64, 2, 3]);
+    assert_eq!(Vec::<i64>::try_from(value), Ok(vec![1, 2, 3]));
+    let mixed = Value::from(vec![Value::Int(1), Value::from("a")]);
+    assert_eq!(
+        Vec::<i64>::try_from(mixed),
+        Err(ConversionError {
+            expected: "int",
+            found: "string"
+        })
+    );
+}
+
+#[test]
+fn maps() {
+    let mut entries = HashMap::new();
+    entries.insert("a".to_owned(), 1.5);
+    entries.insert("b".to_owned(), 2.0);
+    let value = Value::from(entries.clone());
+    assert_eq!(HashMap::<String, f64>::try_from(value), Ok(entries));
+}
+
+#[test]
+fn maps_need_string_keys() {
+    let mut interpreter = interpreter();
+    interpreter.eval(r#"let m = {1: "a"};"#).unwrap();
+    let map = interpreter.get_global("m").unwrap();
+    assert_eq!(
+        HashMap::<String, String>::try_from(map),
+        Err(ConversionError {
+            expected: "string",
+            found: "int"
+        })
+    );
+}
+
+#[test]
+fn values_from_scripts() {
+    let mut interpreter = interpreter();
+    interpreter
+        .eval(r#"let xs = ["a", "b"]; let m = {"k": [1, 2]};"#)
+        .unwrap();
+    let xs = Vec::<String>::try_from(interpreter.get_global("xs").unwrap());
+    assert_eq!(xs, Ok(vec!["a".to_owned(), "b".to_owned()]));
+    let m = HashMap::<String, Vec<i64>>::try_from(interpreter.get_global("m").unwrap()).unwrap();
+    assert_eq!(m["k"], vec![1, 2]);
+}
+
+#[test]
+fn values_into_scripts() {
+    let mut interpreter = interpreter();
+    interpreter.set_global("xs", Value::from(vec!["x", "y"]));
+    interpreter.set_global("n", Value::from(Some(4i64)));
+    interpreter
+        .eval(r#"print xs; print n + len(xs); print xs[0] == "x";"#)
+        .unwrap();
+    assert_eq!(interpreter.take_output(), "[\"x\", \"y\"]\n6\ntrue\n");
+}
+
+#[test]
+fn error_messages() {
+    let err = String::try_from(Value::Nil).unwrap_err();
+    assert_eq!(err.to_string(), "Expected string, got nil");
+    assert_eq!(String::from(err), "Expected string, got nil");
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{expect_runtime_error, interpreter};
+use rlox::{Arity, InterpretError, NativeResult, Value, VM};
+
+#[test]
+fn state_persists_between_evals() {
+    let mut interpreter = interpreter();
+    interpreter.eval("let x = 1;").unwrap();
+    interpreter.eval("x = x + 1;").unwrap();
+    interpreter.eval("print x;").unwrap();
+    assert_eq!(interpreter.take_output(), "2\n");
+}
+
+#[test]
+fn get_and_set_globals() {
+    let mut interpreter = interpreter();
+    interpreter.set_global("limit", Value::Int(3));
+    let name = interpreter.new_string("host");
+    interpreter.set_global("name", name);
+    interpreter
+        .eval(r#"let result = "${name}:${limit * 2}";"#)
+        .unwrap();
+    let expected = interpreter.new_string("host:6");
+    assert_eq!(interpreter.get_global("result"), Some(expected));
+    assert_eq!(interpreter.get_global("missing"), None);
+}
+
+fn double(_vm: &mut VM, args: &[Value]) -> NativeResult {
+    match args[0] {
+        Value::Int(n) => Ok(Value::Int(n * 2)),
+        _ => Err("double() expects an int".to_owned().into()),
+    }
+}
+
+#[test]
+fn register_native() {
+    let mut interpreter = interpreter();
+    interpreter.register_native("double", Arity::Exactly(1), double);
+    interpreter.eval("print double(21);").unwrap();
+    assert_eq!(interpreter.take_output(), "42\n");
+
+    let source = r#"double("a");"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.message, "double() expects an int");
+    let source = "double(1, 2);";
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.message, "double() expects 1 arguments but got 2");
+}
+
+#[test]
+fn errors_leave_the_interpreter_usable() {
+    let mut interpreter = interpreter();
+    interpreter.eval("let x = 1;").unwrap();
+    match interpreter.eval("print x +;") {
+        Err(InterpretError::CompileError(err)) => assert_eq!(err.errors.len(), 1),
+        other => panic!("expected a compile error, got {:?}", other),
+    }
+    assert!(interpreter.eval("{ let y = 1; print undefined; }").is_err());
+    interpreter.eval("{ let z = 2; print x + z; }").unwrap();
+    assert_eq!(interpreter.take_output(), "3\n");
+}
+
+#[test]
+fn runtime_errors_have_lines() {
+    let mut interpreter = interpreter();
+    let source = "let x = 1;\n\nprint -nil;";
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.line, 3);
+    assert_eq!(err.file, None);
+}
+
+#[test]
+fn compile_errors_keep_interned_strings() {
+    let mut interpreter = interpreter();
+    interpreter.eval(r#"let greeting = "hello";"#).unwrap();
+    assert!(interpreter.eval(r#"let broken = "hello" +;"#).is_err());
+    interpreter
+        .eval(
+            r#"let words = ["hello", "world", "hello"];
+               let joined = "";
+               for (let i = 0; i < len(words); i = i + 1) { joined = joined + words[i] + " "; }
+               print joined;
+               print words[0] == greeting;
+               print str(1) + "hello" == "1" + greeting;"#,
+        )
+        .unwrap();
+    assert_eq!(
+        interpreter.take_output(),
+        "hello world hello \ntrue\ntrue\n"
+    );
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use common::{expect_runtime_error, interpreter, run, runtime_error, syntax_errors};
+use rlox::{Capabilities, Interpreter, RuntimeErrorKind};
+
+#[test]
+fn catch_thrown_values() {
+    assert_eq!(
+        run(r#"try { throw "x"; print "skipped"; } catch (e) { print e; } print "after";"#),
+        "x\nafter\n"
+    );
+    assert_eq!(run("try { print 1; } catch (e) { print e; }"), "1\n");
+}
+
+#[test]
+fn catch_runtime_errors() {
+    assert_eq!(
+        run("try {\n  print -nil;\n} catch (e) { print e.message; print e.line; print e; }"),
+        "Operand must be a number\n2\n<error: Operand must be a number>\n"
+    );
+    assert_eq!(
+        run("try { let a = 1;\n\n print undefined; } catch (e) { print e.message; print e.line; }"),
+        "Tried to get value of undefined variable 'undefined'\n3\n"
+    );
+}
+
+#[test]
+fn finally_runs_on_every_path() {
+    assert_eq!(
+        run(r#"try { print "t"; } finally { print "f"; }"#),
+        "t\nf\n"
+    );
+    assert_eq!(
+        run(r#"try { throw 1; } catch (e) { print "c"; } finally { print "f"; }"#),
+        "c\nf\n"
+    );
+    assert_eq!(run(r#"try { } finally { print "only"; }"#), "only\n");
+}
+
+#[test]
+fn empty_catch_followed_by_finally() {
+    assert_eq!(
+        run(r#"try { throw 1; } catch (e) { } finally { print "f"; }"#),
+        "f\n"
+    );
+}
+
+#[test]
+fn catch_block_starting_with_a_block() {
+    assert_eq!(
+        run("try { throw 1; } catch (e) { { print e; } } finally {}"),
+        "1\n"
+    );
+}
+
+#[test]
+fn nested_finally_rethrows() {
+    let mut interpreter = interpreter();
+    let source = r#"try { try { throw "in"; } finally { print "f1"; } } finally { print "f2"; }"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.message, "Uncaught exception: in");
+    assert_eq!(interpreter.take_output(), "f1\nf2\n");
+
+    assert_eq!(
+        run(r#"try { try { throw "in"; } finally { print "f"; } } catch (e) { print e; }"#),
+        "f\nin\n"
+    );
+}
+
+#[test]
+fn throw_from_catch_runs_finally() {
+    let mut interpreter = interpreter();
+    let source = r#"try { throw 1; } catch (e) { throw e + 1; } finally { print "f"; }"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.message, "Uncaught exception: 2");
+    assert_eq!(interpreter.take_output(), "f\n");
+
+    assert_eq!(
+        run(
+            "try { try { throw 1; } catch (e) { throw e + 1; } finally { print 0; } }
+             catch (e) { print e; }"
+        ),
+        "0\n2\n"
+    );
+}
+
+#[test]
+fn throw_from_catch_without_finally() {
+    assert_eq!(
+        runtime_error("try { throw 1; } catch (e) { throw e + 1; }"),
+        "Uncaught exception: 2"
+    );
+    assert_eq!(
+        run("try { try { throw 1; } catch (e) { print -nil; } } catch (e) { print e.message; }"),
+        "Operand must be a number\n"
+    );
+}
+
+#[test]
+fn uncaught_errors_keep_their_message_and_line() {
+    let source = "let a = 1;\ntry { throw a; } finally { }\n";
+    let err = expect_runtime_error(source, interpreter().eval(source).unwrap_err());
+    assert_eq!(err.message, "Uncaught exception: 1");
+    assert_eq!(err.line, 2);
+
+    let source = "try {\n\n print -nil; } finally { }";
+    let err = expect_runtime_error(source, interpreter().eval(source).unwrap_err());
+    assert_eq!(err.message, "Operand must be a number");
+    assert_eq!(err.line, 3);
+}
+
+#[test]
+fn exit_cant_be_caught() {
+    let mut interpreter = interpreter();
+    let source = r#"try { exit(2); } catch (e) { print "caught"; } finally { print "f"; }"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::Exit(2));
+    assert_eq!(interpreter.take_output(), "");
+}
+
+#[test]
+fn limits_cant_be_caught() {
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(100));
+    let source = r#"try { while (true) {} } catch (e) { print "caught"; } finally { print "f"; }"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::BudgetExhausted);
+    assert_eq!(interpreter.take_output(), "");
+}
+
+#[test]
+fn unwinding_drops_block_locals() {
+    assert_eq!(
+        run(r#"{ let a = 1;
+                 try { let b = 2; { let c = 3; throw "x"; } }
+                 catch (e) { let d = 4; print e; print a + d; }
+                 let z = 9; print a; print z; }"#),
+        "x\n5\n1\n9\n"
+    );
+}
+
+#[test]
+fn unwinding_drops_for_in_locals() {
+    assert_eq!(
+        run("{ let a = 1;
+               try { for (let x in [1, 2, 3]) { let y = x; if (x == 2) throw x; } }
+               catch (e) { print e; }
+               for (let x in [7]) print x;
+               let b = 2; print a + b; }"),
+        "2\n7\n3\n"
+    );
+}
+
+#[test]
+fn handlers_are_popped_after_the_try() {
+    assert_eq!(
+        runtime_error("try { } catch (e) { print e; } throw 1;"),
+        "Uncaught exception: 1"
+    );
+}
+
+#[test]
+fn syntax() {
+    assert_eq!(
+        syntax_errors("try { print 1; }"),
+        vec!["Expected 'catch' or 'finally' after try block."]
+    );
+    assert_eq!(
+        syntax_errors("try { } catch e { }"),
+        vec!["Expected '(' after 'catch'."]
+    );
+}
+
+#[test]
+fn denied_natives_can_be_caught() {
+    let mut interpreter = Interpreter::with_capabilities(Capabilities::none());
+    interpreter.capture_output();
+    interpreter
+        .eval(r#"try { env("HOME"); } catch (e) { print e.message; }"#)
+        .unwrap();
+    assert_eq!(
+        interpreter.take_output(),
+        "This script isn't allowed to read environment variables\n"
+    );
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn over_lists() {
+    assert_eq!(run("for (let x in [1, 2]) print x;"), "1\n2\n");
+}
+
+#[test]
+fn over_string_characters() {
+    assert_eq!(run(r#"for (let c in "héy") print c;"#), "h\né\ny\n");
+}
+
+#[test]
+fn over_map_keys() {
+    assert_eq!(run(r#"for (let k in {"a": 1, "b": 2}) print k;"#), "a\nb\n");
+}
+
+#[test]
+fn over_ranges() {
+    assert_eq!(run("for (let i in 0..3) print i;"), "0\n1\n2\n");
+    assert_eq!(run(r#"for (let i in 3..0) print i; print "end";"#), "end\n");
+    assert_eq!(run("print 0..3; print len(2..5);"), "0..3\n3\n");
+}
+
+#[test]
+fn nested_loops() {
+    assert_eq!(
+        run(r#"for (let a in [1, 2]) for (let b in "xy") print "${a}${b}";"#),
+        "1x\n1y\n2x\n2y\n"
+    );
+}
+
+#[test]
+fn loop_variable_shadows_and_is_scoped() {
+    assert_eq!(
+        run(r#"let x = "outer"; for (let x in [1]) { let y = x * 2; print y; } print x;"#),
+        "2\nouter\n"
+    );
+}
+
+#[test]
+fn hidden_locals_are_popped() {
+    assert_eq!(
+        run("{ let a = 1; for (let x in [1, 2]) { let b = x; } let c = 3; print a; print c; }"),
+        "1\n3\n"
+    );
+}
+
+#[test]
+fn list_shrinking_during_iteration_stops_the_loop() {
+    assert_eq!(
+        run("let xs = [1, 2, 3]; for (let x in xs) { if (x == 2) pop(xs); print x; }"),
+        "1\n2\n"
+    );
+}
+
+#[test]
+fn non_iterables() {
+    assert_eq!(
+        runtime_error("for (let x in 5) print x;"),
+        "Values of type int can't be iterated over"
+    );
+    assert_eq!(
+        runtime_error("let r = 0..1.5;"),
+        "Range bounds must be ints, got int and float"
+    );
+}
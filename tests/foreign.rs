@@ -0,0 +1,125 @@
+mod common;
+
+use common::{interpreter, runtime_error};
+use rlox::{Arity, Foreign, ForeignClass, Interpreter, NativeResult, Obj, Value, VM};
+use std::any::Any;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Stands in for a database connection handed to scripts.
+struct Connection {
+    queries: Vec<String>,
+}
+
+thread_local! {
+    static CLOSED: Cell<usize> = Cell::new(0);
+}
+
+fn query(vm: &mut VM, connection: &Foreign, args: &[Value]) -> NativeResult {
+    let sql = match &args[0] {
+        Value::Obj(Obj::String(sql)) => sql.to_string(),
+        _ => return Err("query() expects a string".to_owned().into()),
+    };
+    let mut connection = connection.borrow_mut::<Connection>().unwrap();
+    connection.queries.push(sql);
+    Ok(vm.new_string("ok"))
+}
+
+fn query_count(_vm: &mut VM, connection: &Foreign) -> NativeResult {
+    let connection = connection.borrow::<Connection>().unwrap();
+    Ok(Value::Int(connection.queries.len() as i64))
+}
+
+fn close(data: &mut dyn Any) {
+    assert!(data.is::<Connection>());
+    CLOSED.with(|closed| closed.set(closed.get() + 1));
+}
+
+fn connection_class() -> Rc<ForeignClass> {
+    Rc::new(
+        ForeignClass::new("Connection")
+            .method("query", Arity::Exactly(1), query)
+            // Shadowed by the getter below, which takes priority
+            .method("count", Arity::Exactly(0), query)
+            .getter("count", query_count)
+            .finalizer(close),
+    )
+}
+
+fn with_connection() -> Interpreter {
+    let mut interpreter = interpreter();
+    let connection = Connection { queries: vec![] };
+    let db = Value::Obj(Obj::new_foreign(&connection_class(), connection));
+    interpreter.set_global("db", db);
+    interpreter
+}
+
+#[test]
+fn methods_and_getters() {
+    let mut interpreter = with_connection();
+    interpreter
+        .eval(r#"print db.query("select 1"); print db.count; db.query("select 2");"#)
+        .unwrap();
+    assert_eq!(interpreter.take_output(), "ok\n1\n");
+
+    let db = interpreter.get_global("db").unwrap();
+    let foreign = match &db {
+        Value::Obj(Obj::Foreign(foreign)) => foreign,
+        other => panic!("expected a foreign object, got {:?}", other),
+    };
+    let connection = foreign.borrow::<Connection>().unwrap();
+    assert_eq!(connection.queries, vec!["select 1", "select 2"]);
+    assert!(foreign.borrow::<String>().is_none());
+}
+
+#[test]
+fn bound_methods_remember_their_receiver() {
+    let mut interpreter = with_connection();
+    interpreter
+        .eval(r#"let q = db.query; q("a"); q("b"); print db.count; print q;"#)
+        .unwrap();
+    assert_eq!(interpreter.take_output(), "2\n<method Connection.query>\n");
+}
+
+#[test]
+fn printing_and_identity() {
+    let mut interpreter = with_connection();
+    interpreter
+        .eval("let other = db; print db; print db == other;")
+        .unwrap();
+    assert_eq!(interpreter.take_output(), "<Connection object>\ntrue\n");
+}
+
+#[test]
+fn property_errors() {
+    let mut interpreter = with_connection();
+    let err = interpreter.eval("db.missing;").unwrap_err();
+    assert_eq!(err.to_string(), "[1] Connection has no property 'missing'");
+    let err = interpreter.eval("db.query();").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[1] Connection.query() expects 1 arguments but got 0"
+    );
+    let err = interpreter.eval("db.query(1);").unwrap_err();
+    assert_eq!(err.to_string(), "[1] query() expects a string");
+    assert_eq!(
+        runtime_error("print 1.x;"),
+        "Values of type int don't have properties"
+    );
+}
+
+#[test]
+fn finalizer_runs_when_the_last_reference_goes() {
+    CLOSED.with(|closed| closed.set(0));
+    let mut interpreter = with_connection();
+    interpreter
+        .eval("let other = db; db = nil; { let q = other.query; }")
+        .unwrap();
+    assert_eq!(CLOSED.with(Cell::get), 0);
+    interpreter.eval("other = nil;").unwrap();
+    assert_eq!(CLOSED.with(Cell::get), 1);
+
+    let interpreter = with_connection();
+    drop(interpreter);
+    assert_eq!(CLOSED.with(Cell::get), 2);
+}
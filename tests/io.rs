@@ -0,0 +1,78 @@
+mod common;
+
+use common::{execute, execute_with, run, runtime_error, temp_dir};
+
+#[test]
+fn write_append_and_read_files() {
+    let path = temp_dir("io-files").join("out.txt");
+    let source = format!(
+        r#"let path = "{}"; write_file(path, "one\n"); append_file(path, "two");
+           print read_file(path);"#,
+        path.display()
+    );
+    assert_eq!(run(&source), "one\ntwo\n");
+}
+
+#[test]
+fn io_failures_are_runtime_errors() {
+    assert_eq!(
+        runtime_error(r#"read_file("/nonexistent/rlox/file");"#),
+        "read_file() failed for '/nonexistent/rlox/file': No such file or directory (os error 2)"
+    );
+}
+
+#[test]
+fn read_line_gives_nil_at_end_of_input() {
+    let source = "print read_line(); print read_line(); print read_line();";
+    let output = execute_with(source, &[], "one\r\ntwo");
+    assert_eq!(output.stdout, "one\ntwo\nNil\n");
+}
+
+#[test]
+fn eprint_writes_to_stderr() {
+    let output = execute(r#"print "out"; eprint("err");"#);
+    assert_eq!(output.stdout, "out\n");
+    assert_eq!(output.stderr, "err\n");
+}
+
+#[test]
+fn env_vars() {
+    std::env::set_var("RLOX_TEST_ENV_VAR", "set");
+    assert_eq!(
+        run(r#"print env("RLOX_TEST_ENV_VAR"); print env("RLOX_TEST_UNSET_VAR");"#),
+        "set\nNil\n"
+    );
+}
+
+#[test]
+fn script_args() {
+    let output = execute_with("print args();", &["a", "b c"], "");
+    assert_eq!(output.stdout, "[\"a\", \"b c\"]\n");
+    assert_eq!(run("print args();"), "[]\n");
+}
+
+#[test]
+fn exit_stops_the_script() {
+    let output = execute(r#"print "before"; exit(3); print "after";"#);
+    assert_eq!(output.stdout, "before\n");
+    assert_eq!(output.exit_code, Some(3));
+}
+
+#[test]
+fn exit_defaults_to_zero() {
+    let output = execute(r#"exit(); print "after";"#);
+    assert_eq!(output.stdout, "");
+    assert_eq!(output.exit_code, Some(0));
+}
+
+#[test]
+fn bad_exit_codes() {
+    assert_eq!(
+        runtime_error("exit(9999999999);"),
+        "exit() code 9999999999 is out of range"
+    );
+    assert_eq!(
+        runtime_error(r#"exit("a");"#),
+        "exit() expects an int, got string"
+    );
+}
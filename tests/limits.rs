@@ -0,0 +1,130 @@
+mod common;
+
+use common::{expect_runtime_error, interpreter};
+use rlox::RuntimeErrorKind;
+use std::time::{Duration, Instant};
+
+#[test]
+fn budget_stops_infinite_loops() {
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(1000));
+    let source = "while (true) {}";
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::BudgetExhausted);
+    assert_eq!(err.message, "Instruction budget exhausted");
+    assert_eq!(interpreter.instructions_executed(), 1000);
+}
+
+#[test]
+fn budget_can_be_raised_to_resume() {
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(10));
+    let source = "let i = 0; while (i < 100) { i = i + 1; } print i;";
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::BudgetExhausted);
+    assert_eq!(interpreter.take_output(), "");
+
+    interpreter.set_instruction_budget(None);
+    interpreter.resume().unwrap();
+    assert_eq!(interpreter.take_output(), "100\n");
+}
+
+#[test]
+fn abort_gives_up_on_a_stopped_script() {
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(10));
+    let source = r#"while (true) {} print "unreachable";"#;
+    interpreter.eval(source).unwrap_err();
+
+    interpreter.abort();
+    interpreter.resume().unwrap();
+    assert_eq!(interpreter.take_output(), "");
+    interpreter.eval(r#"print "next";"#).unwrap();
+    assert_eq!(interpreter.take_output(), "next\n");
+}
+
+#[test]
+fn budget_is_enough_for_short_scripts() {
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(1000));
+    interpreter.eval("print 1 + 2;").unwrap();
+    assert_eq!(interpreter.take_output(), "3\n");
+}
+
+#[test]
+fn timeout_stops_infinite_loops() {
+    let mut interpreter = interpreter();
+    interpreter.set_timeout(Duration::from_millis(50));
+    let start = Instant::now();
+    let source = "while (true) {}";
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::Timeout);
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn limits_cant_be_caught() {
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(1000));
+    let source = r#"try { while (true) {} } catch (e) { print "caught"; }"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::BudgetExhausted);
+    assert_eq!(interpreter.take_output(), "");
+}
+
+#[test]
+fn max_stack() {
+    let mut interpreter = interpreter();
+    interpreter.set_max_stack(Some(4));
+    interpreter
+        .eval("{ let a = 1; let b = 2; print a + b; }")
+        .unwrap();
+    assert_eq!(interpreter.take_output(), "3\n");
+
+    let source = "{ let a = 1; let b = 2; let c = 3; let d = 4; let e = 5; let f = 6; }";
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::StackOverflow);
+    assert_eq!(err.message, "Stack overflow");
+}
+
+#[test]
+fn max_heap_bytes() {
+    let mut interpreter = interpreter();
+    interpreter.set_max_heap_bytes(Some(10_000));
+    let source = r#"let s = "x"; while (true) { s = s + s; }"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::OutOfMemory);
+    assert_eq!(err.message, "Out of memory");
+    assert!(interpreter.allocated_bytes() > 10_000);
+}
+
+#[test]
+fn max_heap_bytes_counts_lists() {
+    let mut interpreter = interpreter();
+    interpreter.set_max_heap_bytes(Some(10_000));
+    let source = "let xs = []; while (true) { push(xs, [1, 2, 3]); }";
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::OutOfMemory);
+}
+
+#[test]
+fn memory_limits_cant_be_caught() {
+    let mut interpreter = interpreter();
+    interpreter.set_max_heap_bytes(Some(10_000));
+    let source = r#"try { let s = "x"; while (true) { s = s + s; } }
+                    catch (e) { print "caught"; }"#;
+    let err = expect_runtime_error(source, interpreter.eval(source).unwrap_err());
+    assert_eq!(err.kind, RuntimeErrorKind::OutOfMemory);
+    assert_eq!(interpreter.take_output(), "");
+}
+
+#[test]
+fn heap_count_starts_again_for_each_eval() {
+    let mut interpreter = interpreter();
+    interpreter.set_max_heap_bytes(Some(10_000));
+    let source = r#"let s = ""; for (let i = 0; i < 20; i = i + 1) { s = s + "0123456789"; }"#;
+    for _ in 0..10 {
+        interpreter.eval(source).unwrap();
+    }
+    assert!(interpreter.allocated_bytes() < 10_000);
+}
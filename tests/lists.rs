@@ -0,0 +1,114 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn literals_and_printing() {
+    assert_eq!(run("print []; print [1, 2, 3];"), "[]\n[1, 2, 3]\n");
+    assert_eq!(
+        run(r#"print [[1], "a", nil, 1.5];"#),
+        "[[1], \"a\", Nil, 1.5]\n"
+    );
+}
+
+#[test]
+fn indexing() {
+    assert_eq!(
+        run("let xs = [1, 2, 3]; print xs[0]; print xs[2];"),
+        "1\n3\n"
+    );
+}
+
+#[test]
+fn index_assignment() {
+    assert_eq!(run("let xs = [1, 2]; xs[1] = 5; print xs;"), "[1, 5]\n");
+    assert_eq!(run("let xs = [1, 2]; print xs[0] = 9;"), "9\n");
+}
+
+#[test]
+fn len_push_and_pop() {
+    assert_eq!(
+        run("let xs = [1]; push(xs, 2); print len(xs); print pop(xs); print xs;"),
+        "2\n2\n[1]\n"
+    );
+}
+
+#[test]
+fn lists_are_shared_by_reference() {
+    assert_eq!(
+        run("let a = [1]; let b = a; push(b, 2); print a;"),
+        "[1, 2]\n"
+    );
+}
+
+#[test]
+fn list_containing_itself_prints() {
+    let source = r#"let xs = [1]; push(xs, xs); print xs; print "${xs}";"#;
+    assert_eq!(run(source), "[1, [...]]\n[1, [...]]\n");
+    let source = "let a = [1]; let b = [a]; push(a, b); print a; print [a, a];";
+    assert_eq!(run(source), "[1, [[...]]]\n[[1, [[...]]], [1, [[...]]]]\n");
+}
+
+#[test]
+fn slicing() {
+    assert_eq!(
+        run("let xs = [1, 2, 3, 4]; print xs[1:3]; print xs[:2]; print xs[2:]; print xs[:];"),
+        "[2, 3]\n[1, 2]\n[3, 4]\n[1, 2, 3, 4]\n"
+    );
+}
+
+#[test]
+fn slices_are_copies() {
+    assert_eq!(
+        run("let xs = [1, 2]; let ys = xs[:]; push(ys, 3); print xs;"),
+        "[1, 2]\n"
+    );
+}
+
+#[test]
+fn out_of_bounds_index() {
+    assert_eq!(
+        runtime_error("print [1][5];"),
+        "Index 5 out of bounds for length 1"
+    );
+    assert_eq!(
+        runtime_error("print [1, 2][-1];"),
+        "Index -1 out of bounds for length 2"
+    );
+    assert_eq!(
+        runtime_error("let xs = [1]; xs[1] = 2;"),
+        "Index 1 out of bounds for length 1"
+    );
+}
+
+#[test]
+fn bad_indexes() {
+    assert_eq!(
+        runtime_error(r#"print [1]["a"];"#),
+        "Index must be an int, got string"
+    );
+    assert_eq!(
+        runtime_error("print 5[0];"),
+        "Values of type int can't be indexed"
+    );
+}
+
+#[test]
+fn bad_slices() {
+    assert_eq!(
+        runtime_error("print [1, 2, 3][2:1];"),
+        "Slice start 2 is after its end 1"
+    );
+    assert_eq!(
+        runtime_error("print [1, 2, 3][0:9];"),
+        "Slice bound 9 out of range for length 3"
+    );
+}
+
+#[test]
+fn pop_on_empty_list() {
+    assert_eq!(
+        runtime_error("print pop([]);"),
+        "pop() called on an empty list"
+    );
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{run, syntax_errors};
+
+const UNDERSCORE: &str = "'_' can only be used between digits";
+
+#[test]
+fn radix_literals() {
+    assert_eq!(
+        run("print 0xff; print 0XFF; print 0o17; print 0b101;"),
+        "255\n255\n15\n5\n"
+    );
+}
+
+#[test]
+fn scientific_literals() {
+    assert_eq!(
+        run("print 1e3; print 2.5E-1; print 1e+2;"),
+        "1000.0\n0.25\n100.0\n"
+    );
+}
+
+#[test]
+fn underscores_between_digits() {
+    assert_eq!(
+        run("print 1_000; print 0xff_ff; print 1_0.2_5; print 1e1_0;"),
+        "1000\n65535\n10.25\n10000000000.0\n"
+    );
+}
+
+#[test]
+fn trailing_underscore_is_an_error() {
+    assert_eq!(syntax_errors("print 5_;"), vec![UNDERSCORE]);
+    assert_eq!(syntax_errors("print 5_.5;"), vec![UNDERSCORE]);
+    assert_eq!(syntax_errors("print 0x1_;"), vec![UNDERSCORE]);
+    assert_eq!(syntax_errors("print 1.5_;"), vec![UNDERSCORE]);
+}
+
+#[test]
+fn doubled_or_leading_underscore_is_an_error() {
+    assert_eq!(syntax_errors("print 1__0;"), vec![UNDERSCORE]);
+    assert_eq!(syntax_errors("print 0x_1;"), vec![UNDERSCORE]);
+}
+
+#[test]
+fn malformed_radix_literals() {
+    assert_eq!(
+        syntax_errors("print 0x;"),
+        vec!["Expected digits in hexadecimal literal"]
+    );
+    assert_eq!(
+        syntax_errors("print 0b12;"),
+        vec!["Invalid digit '2' in binary literal"]
+    );
+}
+
+#[test]
+fn malformed_exponent() {
+    assert_eq!(
+        syntax_errors("print 1e;"),
+        vec!["Expected digits in exponent"]
+    );
+}
+
+#[test]
+fn letters_after_a_number_are_an_error() {
+    assert_eq!(
+        syntax_errors("print 12ab;"),
+        vec!["Invalid character in number literal"]
+    );
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn literals_and_printing() {
+    assert_eq!(run("print {};"), "{}\n");
+    assert_eq!(
+        run(r#"print {"a": 1, 2: "b", true: nil, nil: 3};"#),
+        "{\"a\": 1, 2: \"b\", true: Nil, Nil: 3}\n"
+    );
+}
+
+#[test]
+fn braces_at_statement_start_are_still_blocks() {
+    assert_eq!(run("{ let x = 1; print x; } {}"), "1\n");
+}
+
+#[test]
+fn get_and_set() {
+    assert_eq!(
+        run(r#"let m = {"a": 1}; m["a"] = 2; m[nil] = 3;
+               print m["a"]; print m[nil]; print len(m);"#),
+        "2\n3\n2\n"
+    );
+}
+
+#[test]
+fn has_keys_and_remove() {
+    assert_eq!(
+        run(
+            r#"let m = {"b": 1, "a": 2}; print has(m, "a"); print keys(m); print remove(m, "b");
+               print m; print has(m, "b");"#
+        ),
+        "true\n[\"b\", \"a\"]\n1\n{\"a\": 2}\nfalse\n"
+    );
+}
+
+#[test]
+fn iteration_follows_insertion_order() {
+    assert_eq!(
+        run(r#"print keys({"z": 1, "a": 2, 5: 3});"#),
+        "[\"z\", \"a\", 5]\n"
+    );
+}
+
+#[test]
+fn map_containing_itself_prints() {
+    let source = r#"let m = {"a": 1}; m["self"] = m; print m; print "${m}";"#;
+    assert_eq!(
+        run(source),
+        "{\"a\": 1, \"self\": {...}}\n{\"a\": 1, \"self\": {...}}\n"
+    );
+    let source = r#"let m = {}; let xs = [m]; m["xs"] = xs; print m; print xs;"#;
+    assert_eq!(run(source), "{\"xs\": [{...}]}\n[{\"xs\": [...]}]\n");
+}
+
+#[test]
+fn whole_floats_and_ints_are_the_same_key() {
+    assert_eq!(
+        run(r#"let m = {1: "a"}; print m[1.0]; m[2.0] = "b"; print m[2]; print len(m);"#),
+        "a\nb\n2\n"
+    );
+}
+
+#[test]
+fn large_ints_are_not_rounded_to_floats() {
+    assert_eq!(
+        run("print 9007199254740993 == 9007199254740992.0;"),
+        "false\n"
+    );
+    assert_eq!(
+        run("print 9007199254740992 == 9007199254740992.0;"),
+        "true\n"
+    );
+    assert_eq!(
+        run("print 9223372036854775807 == 9223372036854775807.0;"),
+        "false\n"
+    );
+    assert_eq!(
+        run("let m = {9007199254740993: 1}; print has(m, 9007199254740992.0);"),
+        "false\n"
+    );
+}
+
+#[test]
+fn smallest_int_key_matches_its_float() {
+    assert_eq!(
+        run("let m = {-9223372036854775807 - 1: 1}; print has(m, -9223372036854775808.0);"),
+        "true\n"
+    );
+}
+
+#[test]
+fn missing_keys() {
+    assert_eq!(
+        runtime_error(r#"print {"a": 1}["b"];"#),
+        "Key b not found in map"
+    );
+    assert_eq!(
+        runtime_error(r#"print remove({}, "z");"#),
+        "Key z not found in map"
+    );
+}
+
+#[test]
+fn unhashable_keys() {
+    assert_eq!(
+        runtime_error("print {[1]: 2};"),
+        "A list can't be used as a map key"
+    );
+    assert_eq!(
+        runtime_error("let m = {}; m[0/0] = 1;"),
+        "NaN can't be used as a map key"
+    );
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn powers_and_roots() {
+    assert_eq!(
+        run("print sqrt(16); print pow(2, 10); print pow(2, 0.5); print sqrt(-1);"),
+        "4.0\n1024\n1.4142135623730951\nNaN\n"
+    );
+}
+
+#[test]
+fn rounding_gives_ints() {
+    assert_eq!(
+        run(
+            "print floor(1.5); print floor(-1.5); print ceil(1.2); print round(2.5);
+             print round(-2.5); print floor(3);"
+        ),
+        "1\n-2\n2\n3\n-3\n3\n"
+    );
+}
+
+#[test]
+fn rounding_out_of_int_range_stays_float() {
+    assert_eq!(run("print floor(1e300);"), "1e300\n");
+}
+
+#[test]
+fn abs_min_max() {
+    assert_eq!(
+        run("print abs(-3); print abs(-2.5); print min(3, 1, 2); print max(1, 2.5);"),
+        "3\n2.5\n1\n2.5\n"
+    );
+}
+
+#[test]
+fn trig_exp_and_log() {
+    assert_eq!(
+        run("print sin(0); print cos(0); print atan2(1, 1) * 4 == pi; print exp(0); print log(1);"),
+        "0.0\n1.0\ntrue\n1.0\n0.0\n"
+    );
+}
+
+#[test]
+fn constants() {
+    assert_eq!(
+        run("print pi; print inf; print -inf; print nan;"),
+        "3.141592653589793\ninf\n-inf\nNaN\n"
+    );
+}
+
+#[test]
+fn constants_can_be_shadowed_but_not_assigned() {
+    assert_eq!(run("let pi = 3; print pi;"), "3\n");
+    assert_eq!(
+        runtime_error("pi = 3;"),
+        "Tried to assign to undefined variable 'pi'"
+    );
+}
+
+#[test]
+fn seeded_random_is_repeatable() {
+    assert_eq!(
+        run("seed(42); let a = random(); let b = random_int(1, 6);
+             seed(42); print a == random(); print b == random_int(1, 6);
+             print a >= 0 and a < 1; print b >= 1 and b < 6;"),
+        "true\ntrue\ntrue\ntrue\n"
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(runtime_error("print pow(2, 63);"), "Integer overflow");
+    assert_eq!(
+        runtime_error("print abs(-9223372036854775807 - 1);"),
+        "Integer overflow"
+    );
+    assert_eq!(
+        runtime_error(r#"print sqrt("a");"#),
+        "sqrt() expects a number, got string"
+    );
+    assert_eq!(
+        runtime_error("print min();"),
+        "min() expects at least 1 arguments but got 0"
+    );
+    assert_eq!(
+        runtime_error("print random_int(5, 1);"),
+        "random_int() needs low < high, got 5 and 1"
+    );
+}
@@ -0,0 +1,303 @@
+mod common;
+
+use common::{expect_runtime_error, interpreter, temp_dir};
+use rlox::{Capabilities, Capability, Interpreter, RuntimeError, RuntimeErrorKind};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes each `(path, source)` under a fresh directory and returns the directory.
+fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = temp_dir(name);
+    for (path, source) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    dir
+}
+
+/// Runs `dir/main.lox` the way the command line does, returning its output.
+fn run_main(interpreter: &mut Interpreter, dir: &Path) -> Result<String, RuntimeError> {
+    let path = dir.join("main.lox");
+    let source = fs::read_to_string(&path).unwrap();
+    interpreter.set_script_path(&path);
+    match interpreter.eval(&source) {
+        Ok(()) => Ok(interpreter.take_output()),
+        Err(err) => Err(expect_runtime_error(&source, err)),
+    }
+}
+
+fn module_path(dir: &Path, path: &str) -> String {
+    dir.join(path).canonicalize().unwrap().display().to_string()
+}
+
+#[test]
+fn imports_resolve_relative_to_the_importing_file() {
+    let dir = fixture(
+        "modules-relative",
+        &[
+            ("main.lox", r#"import "lib/a"; print a.value;"#),
+            ("lib/a.lox", r#"import "b.lox"; let value = b.value + 1;"#),
+            ("lib/b.lox", "let value = 41;"),
+        ],
+    );
+    assert_eq!(run_main(&mut interpreter(), &dir).unwrap(), "42\n");
+}
+
+#[test]
+fn import_under_another_name() {
+    let dir = fixture(
+        "modules-named",
+        &[
+            (
+                "main.lox",
+                r#"import util from "lib/util"; print util.greeting;"#,
+            ),
+            ("lib/util.lox", r#"let greeting = "hi";"#),
+        ],
+    );
+    assert_eq!(run_main(&mut interpreter(), &dir).unwrap(), "hi\n");
+}
+
+#[test]
+fn modules_have_their_own_globals() {
+    let dir = fixture(
+        "modules-globals",
+        &[
+            (
+                "main.lox",
+                r#"let x = "main"; import "m"; print x; print m.x; print m.sees_builtins;"#,
+            ),
+            ("m.lox", "let x = \"module\"; let sees_builtins = sqrt(4);"),
+        ],
+    );
+    assert_eq!(
+        run_main(&mut interpreter(), &dir).unwrap(),
+        "main\nmodule\n2.0\n"
+    );
+}
+
+#[test]
+fn modules_cant_see_the_importers_globals() {
+    let dir = fixture(
+        "modules-isolated",
+        &[
+            ("main.lox", r#"let secret = 1; import "m";"#),
+            ("m.lox", "print secret;"),
+        ],
+    );
+    let err = run_main(&mut interpreter(), &dir).unwrap_err();
+    assert_eq!(
+        err.message,
+        "Tried to get value of undefined variable 'secret'"
+    );
+    assert_eq!(err.file, Some(module_path(&dir, "m.lox")));
+}
+
+#[test]
+fn modules_run_once() {
+    let dir = fixture(
+        "modules-once",
+        &[
+            (
+                "main.lox",
+                r#"import "a"; import "b"; import "a"; print a.n + b.n;"#,
+            ),
+            ("a.lox", r#"import "b"; print "a runs"; let n = 1;"#),
+            ("b.lox", r#"print "b runs"; let n = 2;"#),
+        ],
+    );
+    assert_eq!(
+        run_main(&mut interpreter(), &dir).unwrap(),
+        "b runs\na runs\n3\n"
+    );
+}
+
+#[test]
+fn import_cycles_are_errors() {
+    let dir = fixture(
+        "modules-cycle",
+        &[
+            ("main.lox", r#"import "a";"#),
+            ("a.lox", r#"import "b";"#),
+            ("b.lox", r#"import "a";"#),
+        ],
+    );
+    let err = run_main(&mut interpreter(), &dir).unwrap_err();
+    let a = module_path(&dir, "a.lox");
+    let b = module_path(&dir, "b.lox");
+    assert_eq!(
+        err.message,
+        format!("Import cycle: {} -> {} -> {}", a, b, a)
+    );
+}
+
+#[test]
+fn importing_the_main_script_is_a_cycle() {
+    let dir = fixture(
+        "modules-main-cycle",
+        &[
+            ("main.lox", r#"print "main runs"; import "a";"#),
+            ("a.lox", r#"import "main";"#),
+        ],
+    );
+    let mut interpreter = interpreter();
+    let err = run_main(&mut interpreter, &dir).unwrap_err();
+    let main = module_path(&dir, "main.lox");
+    let a = module_path(&dir, "a.lox");
+    assert_eq!(
+        err.message,
+        format!("Import cycle: {} -> {} -> {}", main, a, main)
+    );
+    assert_eq!(interpreter.take_output(), "main runs\n");
+}
+
+#[test]
+fn runtime_errors_name_the_module() {
+    let dir = fixture(
+        "modules-runtime-error",
+        &[
+            ("main.lox", "import \"m\";"),
+            ("m.lox", "let ok = 1;\nprint -nil;"),
+        ],
+    );
+    let err = run_main(&mut interpreter(), &dir).unwrap_err();
+    assert_eq!(err.file, Some(module_path(&dir, "m.lox")));
+    assert_eq!(err.line, 2);
+    assert_eq!(
+        err.to_string(),
+        format!("[{}:2] {}", module_path(&dir, "m.lox"), err.message)
+    );
+}
+
+#[test]
+fn compile_errors_name_the_module() {
+    let dir = fixture(
+        "modules-compile-error",
+        &[("main.lox", "import \"m\";"), ("m.lox", "\n\nlet = 1;")],
+    );
+    let err = run_main(&mut interpreter(), &dir).unwrap_err();
+    assert_eq!(err.file, Some(module_path(&dir, "m.lox")));
+    assert_eq!(err.line, 3);
+    assert_eq!(err.message, "Error at =: Expected variable name");
+}
+
+#[test]
+fn missing_modules() {
+    let dir = fixture("modules-missing", &[("main.lox", "import \"nope\";")]);
+    let err = run_main(&mut interpreter(), &dir).unwrap_err();
+    assert_eq!(
+        err.message,
+        "Couldn't import 'nope': No such file or directory (os error 2)"
+    );
+}
+
+#[test]
+fn imports_need_the_filesystem_capability() {
+    let dir = fixture(
+        "modules-denied",
+        &[("main.lox", "import \"m\";"), ("m.lox", "let x = 1;")],
+    );
+    let capabilities = Capabilities::all().deny(Capability::Filesystem);
+    let mut interpreter = Interpreter::with_capabilities(capabilities);
+    interpreter.capture_output();
+    let err = run_main(&mut interpreter, &dir).unwrap_err();
+    assert_eq!(err.message, "This script isn't allowed to import modules");
+}
+
+#[test]
+fn limits_inside_an_import_resume_the_module() {
+    let dir = fixture(
+        "modules-budget",
+        &[
+            ("main.lox", r#"import "m"; print m.total;"#),
+            (
+                "m.lox",
+                r#"print "m starts";
+                   let total = 0;
+                   for (let i = 0; i < 100; i = i + 1) { total = total + i; }"#,
+            ),
+        ],
+    );
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(50));
+    let err = run_main(&mut interpreter, &dir).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::BudgetExhausted);
+    assert_eq!(err.file, Some(module_path(&dir, "m.lox")));
+
+    interpreter.set_instruction_budget(None);
+    interpreter.resume().unwrap();
+    assert_eq!(interpreter.take_output(), "m starts\n4950\n");
+}
+
+#[test]
+fn aborting_an_import_runs_the_module_again_next_time() {
+    let dir = fixture(
+        "modules-abort",
+        &[
+            ("main.lox", r#"import "m"; print m.done;"#),
+            (
+                "m.lox",
+                r#"print "m starts"; while (true) {} let done = true;"#,
+            ),
+        ],
+    );
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(50));
+    run_main(&mut interpreter, &dir).unwrap_err();
+    interpreter.abort();
+    interpreter.resume().unwrap();
+    assert_eq!(interpreter.take_output(), "m starts\n");
+
+    fs::write(dir.join("m.lox"), r#"print "m again"; let done = true;"#).unwrap();
+    interpreter.set_instruction_budget(None);
+    assert_eq!(run_main(&mut interpreter, &dir).unwrap(), "m again\ntrue\n");
+}
+
+#[test]
+fn compile_errors_in_modules_keep_interned_strings() {
+    let dir = fixture(
+        "modules-strings",
+        &[
+            ("main.lox", r#"let greeting = "hello"; import "m";"#),
+            ("m.lox", r#"let broken = "hello" +;"#),
+        ],
+    );
+    let mut interpreter = interpreter();
+    run_main(&mut interpreter, &dir).unwrap_err();
+    interpreter
+        .eval(
+            r#"let words = ["hello", "world"];
+               print words[0] + " " + words[1];
+               print words[0] == greeting;"#,
+        )
+        .unwrap();
+    assert_eq!(interpreter.take_output(), "hello world\ntrue\n");
+}
+
+#[test]
+fn modules_resumed_inside_a_try_block_still_catch() {
+    let dir = fixture(
+        "modules-budget-try",
+        &[
+            ("main.lox", r#"import "m"; print "main done";"#),
+            (
+                "m.lox",
+                r#"try {
+                       for (let i = 0; i < 100; i = i + 1) {}
+                       throw "late";
+                   } catch (e) {
+                       print "caught ${e}";
+                   }"#,
+            ),
+        ],
+    );
+    let mut interpreter = interpreter();
+    interpreter.set_instruction_budget(Some(50));
+    let err = run_main(&mut interpreter, &dir).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::BudgetExhausted);
+
+    interpreter.set_instruction_budget(None);
+    interpreter.resume().unwrap();
+    assert_eq!(interpreter.take_output(), "caught late\nmain done\n");
+}
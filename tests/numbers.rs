@@ -0,0 +1,74 @@
+mod common;
+
+use common::{run, runtime_error, syntax_errors};
+
+#[test]
+fn ints_and_floats_print_differently() {
+    assert_eq!(
+        run("print 1; print 1.0; print 1e3; print -0.5;"),
+        "1\n1.0\n1000.0\n-0.5\n"
+    );
+}
+
+#[test]
+fn int_arithmetic_stays_int() {
+    assert_eq!(run("print 2 + 3; print 2 * 3; print 2 - 3;"), "5\n6\n-1\n");
+}
+
+#[test]
+fn mixing_ints_and_floats_gives_floats() {
+    assert_eq!(
+        run("print 1 + 1.5; print 2 * 1.0; print 3 - 0.5;"),
+        "2.5\n2.0\n2.5\n"
+    );
+}
+
+#[test]
+fn ints_are_exact_past_float_precision() {
+    assert_eq!(run("print 9007199254740992 + 1;"), "9007199254740993\n");
+}
+
+#[test]
+fn ints_and_floats_compare_by_value() {
+    assert_eq!(
+        run("print 1 == 1.0; print 3 > 2.5; print 2 < 2.5;"),
+        "true\ntrue\ntrue\n"
+    );
+}
+
+#[test]
+fn overflow_is_an_error() {
+    assert_eq!(
+        runtime_error("print 9223372036854775807 + 1;"),
+        "Integer overflow"
+    );
+    assert_eq!(
+        runtime_error("print -9223372036854775807 - 2;"),
+        "Integer overflow"
+    );
+    assert_eq!(
+        runtime_error("print 9223372036854775807 * 2;"),
+        "Integer overflow"
+    );
+    assert_eq!(runtime_error("print 2 ** 63;"), "Integer overflow");
+    assert_eq!(
+        runtime_error("print -(-9223372036854775807 - 1);"),
+        "Integer overflow"
+    );
+}
+
+#[test]
+fn float_arithmetic_doesnt_overflow_into_errors() {
+    assert_eq!(
+        run("print 9223372036854775807.0 * 2;"),
+        "1.8446744073709552e19\n"
+    );
+}
+
+#[test]
+fn int_literal_out_of_range_is_a_syntax_error() {
+    assert_eq!(
+        syntax_errors("print 10000000000000000000;"),
+        vec!["Integer literal is too large."]
+    );
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn negation() {
+    assert_eq!(
+        run("print -3; print - -2; let x = 4; print -x;"),
+        "-3\n2\n-4\n"
+    );
+}
+
+#[test]
+fn modulo_takes_the_sign_of_the_divisor() {
+    assert_eq!(
+        run("print 7 % 3; print -7 % 3; print 7 % -3; print 7.5 % 2;"),
+        "1\n2\n-2\n1.5\n"
+    );
+}
+
+#[test]
+fn floor_division_rounds_down() {
+    assert_eq!(
+        run("print 7 ~/ 2; print -7 ~/ 2; print 7.5 ~/ 2;"),
+        "3\n-4\n3.0\n"
+    );
+}
+
+#[test]
+fn integer_division_by_zero_is_an_error() {
+    assert_eq!(runtime_error("print 1 % 0;"), "Division by zero");
+    assert_eq!(runtime_error("print 7 ~/ 0;"), "Division by zero");
+}
+
+#[test]
+fn division_always_gives_a_float() {
+    assert_eq!(
+        run("print 7 / 2; print 6 / 2; print 1 / 0;"),
+        "3.5\n3.0\ninf\n"
+    );
+}
+
+#[test]
+fn exponent() {
+    assert_eq!(
+        run("print 2 ** 10; print 2 ** -1; print 2.0 ** 3;"),
+        "1024\n0.5\n8.0\n"
+    );
+}
+
+#[test]
+fn exponent_is_right_associative_and_binds_tighter_than_multiplication() {
+    assert_eq!(run("print 2 ** 3 ** 2; print 1 + 2 * 3 ** 2;"), "512\n19\n");
+}
+
+#[test]
+fn bitwise_operators() {
+    let source = "print 6 & 3; print 6 | 3; print 6 ^ 3; print ~5; print 1 << 4; print -16 >> 2;";
+    assert_eq!(run(source), "2\n7\n5\n-6\n16\n-4\n");
+}
+
+#[test]
+fn bitwise_precedence_follows_c() {
+    assert_eq!(run("print 1 | 2 & 3; print 1 + 1 << 2;"), "3\n8\n");
+}
+
+#[test]
+fn bitwise_operators_need_ints() {
+    assert_eq!(runtime_error("print 1.5 & 1;"), "Operands must be integers");
+}
+
+#[test]
+fn shift_amount_is_checked() {
+    assert_eq!(
+        runtime_error("print 1 << 64;"),
+        "Shift amount must be between 0 and 63"
+    );
+    assert_eq!(
+        runtime_error("print 1 >> -1;"),
+        "Shift amount must be between 0 and 63"
+    );
+}
+
+#[test]
+fn comparisons_with_nan_are_false() {
+    let source = "let nan = 0 / 0; print nan >= 1; print nan <= 1; print nan < 1; print nan > 1; \
+                  print nan == nan;";
+    assert_eq!(run(source), "false\nfalse\nfalse\nfalse\nfalse\n");
+}
+
+#[test]
+fn nan_is_not_equal_to_itself() {
+    assert_eq!(run("let nan = 0 / 0; print nan != nan;"), "true\n");
+}
+
+#[test]
+fn not_equal_less_equal_and_greater_equal() {
+    let source =
+        "print 1 <= 1; print 2 >= 3; print 1 <= 1.5; print 1 != 1.0; print \"a\" != \"b\"; \
+                  print nil != false;";
+    assert_eq!(run(source), "true\nfalse\ntrue\nfalse\ntrue\ntrue\n");
+}
+
+#[test]
+fn ordering_needs_numbers() {
+    assert_eq!(
+        runtime_error("print \"a\" <= 1;"),
+        "Expected two numbers on the stack"
+    );
+}
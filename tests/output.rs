@@ -0,0 +1,91 @@
+use rlox::Interpreter;
+use std::cell::RefCell;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+/// A writer the test can still read from after handing it to the VM.
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn output_to_a_writer() {
+    let sink = Shared::default();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Box::new(sink.clone()));
+    interpreter.eval(r#"print "a"; print 1 + 1;"#).unwrap();
+    assert_eq!(
+        String::from_utf8(sink.0.borrow().clone()).unwrap(),
+        "a\n2\n"
+    );
+}
+
+#[test]
+fn output_to_a_callback() {
+    let lines = Rc::new(RefCell::new(String::new()));
+    let mut interpreter = Interpreter::new();
+    let callback_lines = lines.clone();
+    interpreter.set_output_fn(move |text| callback_lines.borrow_mut().push_str(text));
+    interpreter.eval(r#"print "héllo"; print [1];"#).unwrap();
+    assert_eq!(*lines.borrow(), "héllo\n[1]\n");
+}
+
+#[test]
+fn take_output_empties_the_buffer() {
+    let mut interpreter = Interpreter::new();
+    interpreter.capture_output();
+    interpreter.eval("print 1;").unwrap();
+    assert_eq!(interpreter.take_output(), "1\n");
+    assert_eq!(interpreter.take_output(), "");
+    interpreter.eval("print 2;").unwrap();
+    assert_eq!(interpreter.take_output(), "2\n");
+}
+
+#[test]
+fn output_before_an_error_is_kept() {
+    let mut interpreter = Interpreter::new();
+    interpreter.capture_output();
+    assert!(interpreter
+        .eval(r#"print "first"; print -nil; print "second";"#)
+        .is_err());
+    assert_eq!(interpreter.take_output(), "first\n");
+}
+
+#[test]
+fn trace_output_is_separate() {
+    let trace = Shared::default();
+    let mut interpreter = Interpreter::new();
+    interpreter.capture_output();
+    interpreter.set_trace_output(Box::new(trace.clone()));
+    interpreter.eval("print 1;").unwrap();
+    assert_eq!(interpreter.take_output(), "1\n");
+    if !cfg!(feature = "trace_execution") {
+        assert!(trace.0.borrow().is_empty());
+    }
+}
+
+#[test]
+fn eprint_to_a_writer() {
+    let errors = Shared::default();
+    let mut interpreter = Interpreter::new();
+    interpreter.capture_output();
+    interpreter.set_error_output(Box::new(errors.clone()));
+    interpreter
+        .eval(r#"print "out"; eprint("oops"); eprint(1 + 1);"#)
+        .unwrap();
+    assert_eq!(interpreter.take_output(), "out\n");
+    assert_eq!(
+        String::from_utf8(errors.0.borrow().clone()).unwrap(),
+        "oops\n2\n"
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn length_and_indexing_count_characters() {
+    assert_eq!(
+        run(r#"print len("héllo"); print "héllo"[1]; print "héllo"[1:3];"#),
+        "5\né\nél\n"
+    );
+}
+
+#[test]
+fn substring() {
+    assert_eq!(
+        run(r#"print substring("héllo", 1); print substring("héllo", 1, 3);"#),
+        "éllo\nél\n"
+    );
+}
+
+#[test]
+fn find() {
+    assert_eq!(
+        run(r#"print find("héllo", "l"); print find("abc", "z");"#),
+        "2\nNil\n"
+    );
+}
+
+#[test]
+fn split() {
+    assert_eq!(
+        run(r#"print split("a,b,,c", ",");"#),
+        "[\"a\", \"b\", \"\", \"c\"]\n"
+    );
+    assert_eq!(run(r#"print split("abc", "");"#), "[\"a\", \"b\", \"c\"]\n");
+}
+
+#[test]
+fn trim_upper_lower() {
+    assert_eq!(
+        run(r#"print trim("  x \n"); print upper("straße"); print lower("ÀB");"#),
+        "x\nSTRASSE\nàb\n"
+    );
+}
+
+#[test]
+fn replace_and_affixes() {
+    assert_eq!(
+        run(
+            r#"print replace("aaa", "a", "b"); print starts_with("héllo", "hé");
+               print ends_with("x", "xy");"#
+        ),
+        "bbb\ntrue\nfalse\n"
+    );
+}
+
+#[test]
+fn number_string_conversions() {
+    assert_eq!(
+        run(r#"print str(1.0) + str(nil) + str([1]);"#),
+        "1.0Nil[1]\n"
+    );
+    assert_eq!(
+        run(r#"print parse_number("42"); print parse_number(" 1.5 "); print parse_number("abc");"#),
+        "42\n1.5\nNil\n"
+    );
+}
+
+#[test]
+fn str_of_self_containing_collections() {
+    let source = r#"let xs = [1]; push(xs, xs); let m = {}; m["m"] = m; print str(xs) + str(m);"#;
+    assert_eq!(run(source), "[1, [...]]{\"m\": {...}}\n");
+}
+
+#[test]
+fn argument_errors() {
+    assert_eq!(
+        runtime_error("print upper(1);"),
+        "upper() expects a string, got int"
+    );
+    assert_eq!(
+        runtime_error(r#"print substring("abc");"#),
+        "substring() expects 2 to 3 arguments but got 1"
+    );
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{run, syntax_errors};
+
+#[test]
+fn simple_escapes() {
+    assert_eq!(run(r#"print "a\tb\\c\"d\'e\$f";"#), "a\tb\\c\"d'e$f\n");
+    assert_eq!(run(r#"print "one\ntwo\r";"#), "one\ntwo\r\n");
+}
+
+#[test]
+fn unicode_escapes() {
+    assert_eq!(
+        run(r#"print "\u{41}\u{e9}\u{1F600}";"#),
+        "A\u{e9}\u{1F600}\n"
+    );
+}
+
+#[test]
+fn strings_can_span_lines() {
+    assert_eq!(run("print \"one\ntwo\";"), "one\ntwo\n");
+}
+
+#[test]
+fn invalid_escapes() {
+    assert_eq!(
+        syntax_errors(r#"print "\q";"#),
+        vec![r"Invalid escape sequence '\q'"]
+    );
+    assert_eq!(
+        syntax_errors(r#"print "\u41";"#),
+        vec![r"Expected '{' after '\u'"]
+    );
+    assert_eq!(
+        syntax_errors(r#"print "\u{1234567}";"#),
+        vec!["Unicode escape must be 1 to 6 hex digits followed by '}'"]
+    );
+    assert_eq!(
+        syntax_errors(r#"print "\u{110000}";"#),
+        vec![r"Invalid unicode escape '\u{110000}'"]
+    );
+}
+
+#[test]
+fn interpolation() {
+    assert_eq!(
+        run(r#"let name = "world"; print "Hello ${name}!";"#),
+        "Hello world!\n"
+    );
+    assert_eq!(run(r#"print "${1}${2}";"#), "12\n");
+}
+
+#[test]
+fn interpolation_stringifies_values() {
+    assert_eq!(
+        run(r#"print "${1 + 2} ${1.5} ${nil} ${true} ${[1, "a"]}";"#),
+        "3 1.5 Nil true [1, \"a\"]\n"
+    );
+}
+
+#[test]
+fn nested_interpolation() {
+    assert_eq!(run(r#"let a = "x"; print "o ${"i ${a}"} o";"#), "o i x o\n");
+}
+
+#[test]
+fn braces_inside_interpolation() {
+    assert_eq!(run(r#"print "${ {"k": 1}["k"] }";"#), "1\n");
+}
+
+#[test]
+fn escaped_dollar_is_not_interpolated() {
+    assert_eq!(run(r#"print "\${x}";"#), "${x}\n");
+}
+
+#[test]
+fn malformed_interpolation() {
+    assert_eq!(
+        syntax_errors(r#"print "${}";"#),
+        vec!["Expected '}' after interpolated expression."]
+    );
+    assert_eq!(
+        syntax_errors(r#"print "${1";"#),
+        vec!["Unterminated String"]
+    );
+}
+
+#[test]
+fn unterminated_string() {
+    assert_eq!(syntax_errors(r#"print "abc"#), vec!["Unterminated String"]);
+}
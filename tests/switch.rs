@@ -0,0 +1,132 @@
+mod common;
+
+use common::{run, syntax_errors};
+
+#[test]
+fn runs_matching_case_only() {
+    let source = r#"
+        switch (2) {
+            case 1: print "one";
+            case 2: print "two";
+            case 3: print "three";
+            default: print "other";
+        }
+    "#;
+    assert_eq!(run(source), "two\n");
+}
+
+#[test]
+fn falls_back_to_default() {
+    let source = r#"
+        switch ("z") {
+            case "a": print "a";
+            default: print "default";
+        }
+    "#;
+    assert_eq!(run(source), "default\n");
+}
+
+#[test]
+fn no_match_without_default_does_nothing() {
+    assert_eq!(
+        run("switch (9) { case 1: print 1; } print \"after\";"),
+        "after\n"
+    );
+}
+
+#[test]
+fn empty_labels_share_the_next_body() {
+    let source = r#"
+        for (let x = 1; x <= 3; x = x + 1) {
+            switch (x) {
+                case 1:
+                case 2: print "small";
+                case 3: print "three";
+            }
+        }
+    "#;
+    assert_eq!(run(source), "small\nsmall\nthree\n");
+}
+
+#[test]
+fn empty_last_case_finishes() {
+    assert_eq!(run("switch (1) { case 1: } print \"done\";"), "done\n");
+    assert_eq!(
+        run("switch (2) { case 1: print 1; case 2: } print \"done\";"),
+        "done\n"
+    );
+}
+
+#[test]
+fn empty_default_finishes() {
+    assert_eq!(
+        run("switch (5) { case 1: print 1; default: } print \"done\";"),
+        "done\n"
+    );
+}
+
+#[test]
+fn dense_int_labels_use_jump_table() {
+    let source = r#"
+        for (let x = -1; x < 7; x = x + 1) {
+            switch (x) {
+                case 0: print "zero";
+                case 1: print "one";
+                case 2:
+                case 3: print "two or three";
+                case 5: print "five";
+                default: print "other";
+            }
+        }
+    "#;
+    let expected = "other\nzero\none\ntwo or three\ntwo or three\nother\nfive\nother\n";
+    assert_eq!(run(source), expected);
+}
+
+#[test]
+fn empty_last_case_in_jump_table_finishes() {
+    let source = "switch (3) { case 0: print 0; case 1: print 1; case 2: print 2; case 3: } \
+                  print \"done\";";
+    assert_eq!(run(source), "done\n");
+}
+
+#[test]
+fn jump_table_offsets_out_of_range_are_reported() {
+    let source = format!(
+        "switch (0) {{ case 0: {} case 1: print 1; case 2: print 2; case 3: print 3; }}",
+        "print nil;".repeat(40_000)
+    );
+    let errors = syntax_errors(&source);
+    assert!(
+        errors
+            .iter()
+            .any(|error| error.ends_with("is too large for jump table")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn case_bodies_can_declare_locals() {
+    let source = r#"
+        {
+            let before = "kept";
+            switch (1) {
+                case 1:
+                    let inside = "inside";
+                    print inside;
+            }
+            print before;
+        }
+    "#;
+    assert_eq!(run(source), "inside\nkept\n");
+}
+
+#[test]
+fn rejects_duplicate_and_non_constant_labels() {
+    let errors = syntax_errors("switch (1) { case 1: print 1; case 1: print 2; }");
+    assert_eq!(errors, vec!["Duplicate case value in switch"]);
+
+    let errors = syntax_errors("let y = 1; switch (1) { case y: print 1; }");
+    assert_eq!(errors, vec!["Case value must be a constant literal"]);
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn locals_resolve_to_their_own_slots() {
+    assert_eq!(run("{ let a = 1; let b = 2; print a; print b; }"), "1\n2\n");
+}
+
+#[test]
+fn inner_locals_shadow_outer_ones() {
+    let source = "{ let a = \"outer\"; { let a = \"inner\"; print a; } print a; }";
+    assert_eq!(run(source), "inner\nouter\n");
+}
+
+#[test]
+fn for_loop_variable_is_popped_after_the_loop() {
+    let source = r#"
+        {
+            let before = "before";
+            for (let i = 0; i < 3; i = i + 1) {}
+            let after = "after";
+            print before;
+            print after;
+        }
+    "#;
+    assert_eq!(run(source), "before\nafter\n");
+}